@@ -0,0 +1,142 @@
+use fuel_vm::{
+    fuel_types::BlockHeight,
+    prelude::{Address, ContractId, Instruction, TxId},
+};
+
+use crate::{storage_access::StorageJournal, BlockTracer, TraceFlow, VmView};
+
+/// Which part of a transaction is being executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionPhase {
+    /// Predicate of the input at `input_index`, owned by `owner`
+    Predicate { input_index: usize, owner: Address },
+    /// The script of a script transaction
+    Script,
+}
+
+/// Position of the traced instruction within the block, passed to callbacks alongside the VM
+#[derive(Debug, Clone)]
+pub struct TraceContext {
+    /// Height of the block being traced
+    pub block_height: BlockHeight,
+    /// Id of the transaction being executed
+    pub tx_id: TxId,
+    /// Index of the transaction within the block
+    pub tx_index: usize,
+    /// Which part of the transaction is being executed
+    pub phase: ExecutionPhase,
+    /// Number of instructions traced before this one in the whole block
+    pub instruction_index: u64,
+    /// Number of instructions traced before this one in the current transaction
+    pub tx_instruction_index: u64,
+    /// Number of call frames on the stack, zero when executing the script or a predicate itself
+    pub call_depth: usize,
+    /// Set on the last step of a phase, after the program has returned,
+    /// when there is no instruction left to execute
+    pub finished: bool,
+    /// Number of receipts of the current phase already passed to the tracer
+    seen_receipts: usize,
+    /// Storage accesses not yet passed to the tracer, if they are recorded
+    journal: Option<StorageJournal>,
+    /// Index within the transaction and contract of the last instruction of the current phase,
+    /// which storage accesses recorded since are attributed to
+    last_instruction: Option<(u64, Option<ContractId>)>,
+}
+
+impl TraceContext {
+    pub(crate) fn new(block_height: BlockHeight, journal: Option<StorageJournal>) -> Self {
+        Self {
+            block_height,
+            tx_id: TxId::zeroed(),
+            tx_index: 0,
+            phase: ExecutionPhase::Script,
+            instruction_index: 0,
+            tx_instruction_index: 0,
+            call_depth: 0,
+            finished: false,
+            seen_receipts: 0,
+            journal,
+            last_instruction: None,
+        }
+    }
+
+    /// Moves the context to the start of the given transaction
+    pub(crate) fn start_tx(&mut self, tx_id: TxId, tx_index: usize) {
+        self.tx_id = tx_id;
+        self.tx_index = tx_index;
+        self.tx_instruction_index = 0;
+        // Accesses of the previous transaction that weren't traced are dropped
+        if let Some(journal) = &self.journal {
+            journal.take();
+        }
+        self.start_phase(ExecutionPhase::Script);
+    }
+
+    /// Moves the context to the start of the given phase of the current transaction
+    pub(crate) fn start_phase(&mut self, phase: ExecutionPhase) {
+        self.phase = phase;
+        self.call_depth = 0;
+        self.finished = false;
+        self.seen_receipts = 0;
+        self.last_instruction = None;
+    }
+
+    /// Turns recording of storage accesses on or off, if they are recorded at all
+    pub(crate) fn record_storage(&self, recording: bool) {
+        if let Some(journal) = &self.journal {
+            journal.set_recording(recording);
+        }
+    }
+
+    /// Index within the transaction of the last instruction passed to the tracer in the current phase,
+    /// which is the one the interpreter executes next
+    pub(crate) fn last_instruction_index(&self) -> Option<u64> {
+        self.last_instruction.map(|(index, _)| index)
+    }
+
+    /// Passes new storage accesses, receipts and the current instruction to the tracer,
+    /// and advances the counters past it. `finished` marks the step after the program returned,
+    /// where the memory at `$pc` is whatever follows the last instruction.
+    /// Stops at the first hook that doesn't return [`TraceFlow::Continue`].
+    pub(crate) fn step<T>(&mut self, vm: &dyn VmView, tracer: &mut T, finished: bool) -> TraceFlow
+    where
+        T: BlockTracer + ?Sized,
+    {
+        self.call_depth = vm.call_depth();
+        self.finished = finished;
+        let accesses = self
+            .journal
+            .as_ref()
+            .map(|journal| journal.take())
+            .unwrap_or_default();
+        for mut access in accesses {
+            if let Some((instruction_index, contract_id)) = self.last_instruction {
+                access.instruction_index = Some(instruction_index);
+                access.contract_id = contract_id;
+            }
+            let flow = tracer.on_storage_access(vm, self, &access);
+            if flow != TraceFlow::Continue {
+                return flow;
+            }
+        }
+        while let Some(receipt) = vm.receipts().get(self.seen_receipts) {
+            let flow = tracer.on_receipt(vm, self, self.seen_receipts, receipt);
+            self.seen_receipts = self.seen_receipts.saturating_add(1);
+            if flow != TraceFlow::Continue {
+                return flow;
+            }
+        }
+        if let Some(Instruction::ECAL(ecal)) = vm.instruction().filter(|_| !finished) {
+            let (a, b, c, d) = ecal.unpack();
+            let flow = tracer.on_ecal(vm, self, [a, b, c, d]);
+            if flow != TraceFlow::Continue {
+                return flow;
+            }
+        }
+        let flow = tracer.on_instruction(vm, self);
+        self.last_instruction = Some((self.tx_instruction_index, vm.contract_id()));
+        self.instruction_index = self.instruction_index.saturating_add(1);
+        self.tx_instruction_index = self.tx_instruction_index.saturating_add(1);
+        flow
+    }
+}
@@ -0,0 +1,103 @@
+use std::io;
+
+use fuel_core_client::client::{
+    pagination::{PageDirection, PaginationRequest},
+    types::{TransactionResponse, TransactionType},
+    FuelClient,
+};
+use fuel_core_types::services::executor::StorageReadReplayEvent;
+use fuel_vm::{
+    fuel_types::{BlockHeight, ChainId},
+    prelude::*,
+};
+
+use super::{block_transaction, BlockInfo, TraceDataSource};
+use crate::TraceError;
+
+/// How many transactions are requested per page when fetching the transactions of a block
+const TX_PAGE_SIZE: usize = 50;
+
+impl TraceDataSource for FuelClient {
+    async fn block_by_height(&self, height: BlockHeight) -> io::Result<Option<BlockInfo>> {
+        Ok(FuelClient::block_by_height(self, height)
+            .await?
+            .map(BlockInfo::from))
+    }
+
+    async fn transaction(&self, id: &TxId) -> io::Result<Option<TransactionResponse>> {
+        FuelClient::transaction(self, id).await
+    }
+
+    async fn consensus_parameters(&self, version: u32) -> io::Result<Option<ConsensusParameters>> {
+        let Ok(version) = i32::try_from(version) else {
+            return Ok(None);
+        };
+        FuelClient::consensus_parameters(self, version).await
+    }
+
+    async fn storage_read_replay(
+        &self,
+        height: BlockHeight,
+    ) -> io::Result<Vec<StorageReadReplayEvent>> {
+        FuelClient::storage_read_replay(self, &height).await
+    }
+
+    /// The transactions after the first one are fetched in pages, starting from the first one.
+    /// If a page doesn't match the expected transaction ids, the rest are fetched one by one.
+    async fn block_transactions(
+        &self,
+        height: BlockHeight,
+        chain_id: &ChainId,
+        tx_ids: &[TxId],
+    ) -> Result<Vec<TransactionResponse>, TraceError> {
+        let Some(first_tx_id) = tx_ids.first() else {
+            return Ok(Vec::new());
+        };
+
+        let mut txs = Vec::with_capacity(tx_ids.len());
+        txs.push(block_transaction(self, first_tx_id).await?);
+
+        // Transactions are paginated by a `height#id` cursor, in block order
+        let mut cursor = Some(format!("{}#{:x}", *height, first_tx_id));
+        'pages: while txs.len() < tx_ids.len() {
+            let Some(after) = cursor.take() else {
+                break;
+            };
+            let remaining = tx_ids.len().saturating_sub(txs.len());
+            let page = self
+                .transactions(PaginationRequest {
+                    cursor: Some(after),
+                    results: i32::try_from(remaining.min(TX_PAGE_SIZE)).unwrap_or(i32::MAX),
+                    direction: PageDirection::Forward,
+                })
+                .await?;
+
+            for tx in page.results {
+                let Some(expected) = tx_ids.get(txs.len()) else {
+                    break 'pages;
+                };
+                match &tx.transaction {
+                    TransactionType::Known(known) if known.id(chain_id) == *expected => {
+                        txs.push(tx)
+                    }
+                    _ => {
+                        tracing::debug!(
+                            "Paginated transactions diverged from block {height} at {expected}"
+                        );
+                        break 'pages;
+                    }
+                }
+            }
+
+            if page.has_next_page {
+                cursor = page.cursor;
+            }
+        }
+
+        for tx_id in &tx_ids[txs.len()..] {
+            txs.push(block_transaction(self, tx_id).await?);
+        }
+
+        Ok(txs)
+    }
+}
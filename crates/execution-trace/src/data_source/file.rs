@@ -0,0 +1,104 @@
+use std::{fs, io, path::Path};
+
+use fuel_core_client::client::types::TransactionResponse;
+use fuel_core_types::services::executor::StorageReadReplayEvent;
+use fuel_vm::{fuel_types::BlockHeight, prelude::*};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{BlockInfo, MemoryDataSource, TraceDataSource};
+
+/// Version of the fixture file format, bumped on incompatible changes
+pub const FIXTURE_VERSION: u32 = 1;
+
+/// Contents of a fixture or snapshot file
+#[derive(Serialize, Deserialize)]
+struct Fixture<Data> {
+    version: u32,
+    data: Data,
+}
+
+/// Data source backed by a JSON fixture file holding a serialized [`MemoryDataSource`].
+/// Fixtures are typically recorded from a node with [`MemoryDataSource::record_block`],
+/// which makes traces reproducible without access to the node.
+#[derive(Debug, Clone)]
+pub struct FileDataSource {
+    data: MemoryDataSource,
+}
+
+impl FileDataSource {
+    /// Reads the whole file into memory
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let data = read_versioned(path, "fixture", FIXTURE_VERSION)?;
+        Ok(Self { data })
+    }
+
+    /// Writes `data` to a fixture file that can later be opened with [`FileDataSource::open`]
+    pub fn save(path: impl AsRef<Path>, data: &MemoryDataSource) -> io::Result<()> {
+        write_versioned(path, FIXTURE_VERSION, data)
+    }
+
+    /// Everything the file contains
+    pub fn data(&self) -> &MemoryDataSource {
+        &self.data
+    }
+}
+
+/// Reads a JSON file written by [`write_versioned`], failing if its version isn't `version`.
+/// `kind` names the file format in the error message.
+pub(crate) fn read_versioned<Data>(
+    path: impl AsRef<Path>,
+    kind: &str,
+    version: u32,
+) -> io::Result<Data>
+where
+    Data: DeserializeOwned,
+{
+    let file = fs::File::open(path)?;
+    let fixture: Fixture<serde_json::Value> = serde_json::from_reader(io::BufReader::new(file))?;
+    if fixture.version != version {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Unsupported {kind} version {}, expected {version}",
+                fixture.version
+            ),
+        ));
+    }
+    Ok(serde_json::from_value(fixture.data)?)
+}
+
+/// Writes `data` to a JSON file, tagged with the version of its format
+pub(crate) fn write_versioned<Data>(
+    path: impl AsRef<Path>,
+    version: u32,
+    data: &Data,
+) -> io::Result<()>
+where
+    Data: Serialize,
+{
+    let file = fs::File::create(path)?;
+    let fixture = Fixture { version, data };
+    serde_json::to_writer(io::BufWriter::new(file), &fixture)?;
+    Ok(())
+}
+
+impl TraceDataSource for FileDataSource {
+    async fn block_by_height(&self, height: BlockHeight) -> io::Result<Option<BlockInfo>> {
+        self.data.block_by_height(height).await
+    }
+
+    async fn transaction(&self, id: &TxId) -> io::Result<Option<TransactionResponse>> {
+        self.data.transaction(id).await
+    }
+
+    async fn consensus_parameters(&self, version: u32) -> io::Result<Option<ConsensusParameters>> {
+        self.data.consensus_parameters(version).await
+    }
+
+    async fn storage_read_replay(
+        &self,
+        height: BlockHeight,
+    ) -> io::Result<Vec<StorageReadReplayEvent>> {
+        self.data.storage_read_replay(height).await
+    }
+}
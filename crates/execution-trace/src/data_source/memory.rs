@@ -0,0 +1,114 @@
+use std::{collections::HashMap, io};
+
+use fuel_core_client::client::types::TransactionResponse;
+use fuel_core_types::services::executor::StorageReadReplayEvent;
+use fuel_vm::{fuel_types::BlockHeight, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{BlockInfo, TraceDataSource};
+use crate::TraceError;
+
+/// Data source holding everything in memory, e.g. for testing tracers without a node
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "MemoryContents", into = "MemoryContents")]
+pub struct MemoryDataSource {
+    pub blocks: HashMap<BlockHeight, BlockInfo>,
+    pub transactions: HashMap<TxId, TransactionResponse>,
+    pub consensus_parameters: HashMap<u32, ConsensusParameters>,
+    pub storage_reads: HashMap<BlockHeight, Vec<StorageReadReplayEvent>>,
+}
+
+impl MemoryDataSource {
+    /// Copies everything needed to trace the block at `height` from another source,
+    /// so that it can be traced later without it
+    pub async fn record_block<D>(
+        &mut self,
+        source: &D,
+        height: BlockHeight,
+    ) -> Result<(), TraceError>
+    where
+        D: TraceDataSource + ?Sized,
+    {
+        let block = source
+            .block_by_height(height)
+            .await?
+            .ok_or(TraceError::NoSuchBlock)?;
+        let storage_reads = source.storage_read_replay(height).await?;
+        let consensus_params = source
+            .consensus_parameters(block.consensus_parameters_version)
+            .await?
+            .ok_or(TraceError::NoConsensusParameters)?;
+        let txs = source
+            .block_transactions(height, &consensus_params.chain_id(), &block.transactions)
+            .await?;
+
+        self.transactions
+            .extend(block.transactions.iter().copied().zip(txs));
+        self.consensus_parameters
+            .insert(block.consensus_parameters_version, consensus_params);
+        self.storage_reads.insert(height, storage_reads);
+        self.blocks.insert(height, block);
+        Ok(())
+    }
+}
+
+impl TraceDataSource for MemoryDataSource {
+    async fn block_by_height(&self, height: BlockHeight) -> io::Result<Option<BlockInfo>> {
+        Ok(self.blocks.get(&height).cloned())
+    }
+
+    async fn transaction(&self, id: &TxId) -> io::Result<Option<TransactionResponse>> {
+        Ok(self.transactions.get(id).cloned())
+    }
+
+    async fn consensus_parameters(&self, version: u32) -> io::Result<Option<ConsensusParameters>> {
+        Ok(self.consensus_parameters.get(&version).cloned())
+    }
+
+    async fn storage_read_replay(
+        &self,
+        height: BlockHeight,
+    ) -> io::Result<Vec<StorageReadReplayEvent>> {
+        self.storage_reads.get(&height).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No storage reads for block {height}"),
+            )
+        })
+    }
+}
+
+/// Serialized form of [`MemoryDataSource`], using lists as not all keys serialize as map keys
+#[derive(Serialize, Deserialize)]
+struct MemoryContents {
+    blocks: Vec<BlockInfo>,
+    transactions: Vec<(TxId, TransactionResponse)>,
+    consensus_parameters: Vec<(u32, ConsensusParameters)>,
+    storage_reads: Vec<(BlockHeight, Vec<StorageReadReplayEvent>)>,
+}
+
+impl From<MemoryContents> for MemoryDataSource {
+    fn from(contents: MemoryContents) -> Self {
+        Self {
+            blocks: contents
+                .blocks
+                .into_iter()
+                .map(|block| (block.height, block))
+                .collect(),
+            transactions: contents.transactions.into_iter().collect(),
+            consensus_parameters: contents.consensus_parameters.into_iter().collect(),
+            storage_reads: contents.storage_reads.into_iter().collect(),
+        }
+    }
+}
+
+impl From<MemoryDataSource> for MemoryContents {
+    fn from(source: MemoryDataSource) -> Self {
+        Self {
+            blocks: source.blocks.into_values().collect(),
+            transactions: source.transactions.into_iter().collect(),
+            consensus_parameters: source.consensus_parameters.into_iter().collect(),
+            storage_reads: source.storage_reads.into_iter().collect(),
+        }
+    }
+}
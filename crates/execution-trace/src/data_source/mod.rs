@@ -0,0 +1,103 @@
+use std::{future::Future, io};
+
+use fuel_core_client::client::types::{Block, TransactionResponse};
+use fuel_core_types::{services::executor::StorageReadReplayEvent, tai64::Tai64};
+use fuel_vm::{
+    fuel_types::{BlockHeight, ChainId},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::TraceError;
+
+mod client;
+mod file;
+mod memory;
+
+pub(crate) use file::{read_versioned, write_versioned};
+pub use file::{FileDataSource, FIXTURE_VERSION};
+pub use memory::MemoryDataSource;
+
+/// The parts of a block needed to replay it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub height: BlockHeight,
+    pub time: Tai64,
+    pub consensus_parameters_version: u32,
+    pub state_transition_bytecode_version: u32,
+    /// Ids of the transactions in the block, the last one being the mint transaction
+    pub transactions: Vec<TxId>,
+}
+
+impl From<Block> for BlockInfo {
+    fn from(block: Block) -> Self {
+        Self {
+            height: block.header.height.into(),
+            time: block.header.time,
+            consensus_parameters_version: block.header.consensus_parameters_version,
+            state_transition_bytecode_version: block.header.state_transition_bytecode_version,
+            transactions: block.transactions,
+        }
+    }
+}
+
+/// Where the tracer gets blocks, transactions and storage reads from.
+/// Implemented for `FuelClient`, and by [`MemoryDataSource`] and [`FileDataSource`]
+/// for tracing without a node.
+pub trait TraceDataSource: Sync {
+    /// Returns `None` if the block doesn't exist
+    fn block_by_height(
+        &self,
+        height: BlockHeight,
+    ) -> impl Future<Output = io::Result<Option<BlockInfo>>> + Send;
+
+    /// Returns `None` if the transaction doesn't exist
+    fn transaction(
+        &self,
+        id: &TxId,
+    ) -> impl Future<Output = io::Result<Option<TransactionResponse>>> + Send;
+
+    /// Returns `None` if there's no such version
+    fn consensus_parameters(
+        &self,
+        version: u32,
+    ) -> impl Future<Output = io::Result<Option<ConsensusParameters>>> + Send;
+
+    /// Storage reads done by fuel-core when it executed the block
+    fn storage_read_replay(
+        &self,
+        height: BlockHeight,
+    ) -> impl Future<Output = io::Result<Vec<StorageReadReplayEvent>>> + Send;
+
+    /// Returns the given consecutive transactions of the block at `height`, in order.
+    /// Fetches them one by one unless overridden.
+    fn block_transactions(
+        &self,
+        height: BlockHeight,
+        chain_id: &ChainId,
+        tx_ids: &[TxId],
+    ) -> impl Future<Output = Result<Vec<TransactionResponse>, TraceError>> + Send {
+        let _ = (height, chain_id);
+        async move {
+            let mut txs = Vec::with_capacity(tx_ids.len());
+            for tx_id in tx_ids {
+                txs.push(block_transaction(self, tx_id).await?);
+            }
+            Ok(txs)
+        }
+    }
+}
+
+/// Fetches a single transaction that's part of a block
+pub(crate) async fn block_transaction<D>(
+    source: &D,
+    tx_id: &TxId,
+) -> Result<TransactionResponse, TraceError>
+where
+    D: TraceDataSource + ?Sized,
+{
+    source
+        .transaction(tx_id)
+        .await?
+        .ok_or(TraceError::MissingTransaction(*tx_id))
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use fuel_vm::prelude::{TxId, Word};
+
+use crate::{ReceiptDiff, TraceError};
+
+/// How to handle transactions whose local replay diverges from what happened on chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplayPolicy {
+    /// Fail the trace with an error
+    #[default]
+    Strict,
+    /// Log a warning, pass a [`TraceDiagnostic`] to the tracer and keep tracing
+    Warn,
+    /// Keep tracing without reporting anything
+    Ignore,
+}
+
+/// Gas used and fee paid by a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasUsage {
+    pub total_gas: Word,
+    pub total_fee: Word,
+}
+
+/// A problem with the replay of a transaction that didn't stop the trace
+#[derive(Debug, Clone)]
+pub enum TraceDiagnostic {
+    /// Local execution produced different receipts than the chain
+    ReceiptsMismatch(Box<ReceiptDiff>),
+    /// Gas or fee computed locally differs from the transaction status
+    GasMismatch {
+        expected: GasUsage,
+        actual: GasUsage,
+    },
+    /// Computing the gas or fee of the local execution overflowed
+    GasOverflow { expected: GasUsage },
+}
+
+impl TraceDiagnostic {
+    /// The error returned for this divergence under [`ReplayPolicy::Strict`]
+    pub(crate) fn into_error(self, tx_id: TxId) -> TraceError {
+        match self {
+            Self::ReceiptsMismatch(diff) => TraceError::ReceiptsMismatch { tx_id, diff },
+            Self::GasMismatch { expected, actual } => TraceError::GasMismatch {
+                tx_id,
+                expected,
+                actual,
+            },
+            Self::GasOverflow { expected } => TraceError::GasOverflow { tx_id, expected },
+        }
+    }
+}
+
+impl fmt::Display for TraceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReceiptsMismatch(diff) => write!(
+                f,
+                "receipts diverged at index {}",
+                diff.first_divergent_index
+            ),
+            Self::GasMismatch { expected, actual } => write!(
+                f,
+                "expected gas {} and fee {}, got gas {} and fee {}",
+                expected.total_gas, expected.total_fee, actual.total_gas, actual.total_fee
+            ),
+            Self::GasOverflow { expected } => write!(
+                f,
+                "expected gas {} and fee {}, computing them locally overflowed",
+                expected.total_gas, expected.total_fee
+            ),
+        }
+    }
+}
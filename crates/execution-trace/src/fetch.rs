@@ -0,0 +1,87 @@
+use fuel_core_client::client::types::{TransactionResponse, TransactionType};
+use fuel_core_types::services::executor::StorageReadReplayEvent;
+use fuel_vm::{
+    fuel_tx::field::{InputContract, MintGasPrice},
+    fuel_types::BlockHeight,
+    prelude::*,
+};
+
+use crate::{
+    data_source::{block_transaction, BlockInfo, TraceDataSource},
+    TraceError,
+};
+
+/// Everything needed to execute a block, fetched ahead of execution
+pub(crate) struct BlockData {
+    pub block: BlockInfo,
+    pub storage_reads: Vec<StorageReadReplayEvent>,
+    pub gas_price: Word,
+    pub coinbase: ContractId,
+    pub consensus_params: ConsensusParameters,
+    /// Transactions of the block, excluding the final mint transaction
+    pub txs: Vec<(TxId, TransactionResponse)>,
+}
+
+/// Fetches a block and its transactions. If `target` is given,
+/// the transactions after it are not fetched, as they won't be executed.
+pub(crate) async fn fetch_block<D>(
+    source: &D,
+    block_height: BlockHeight,
+    target: Option<TxId>,
+) -> Result<BlockData, TraceError>
+where
+    D: TraceDataSource + ?Sized,
+{
+    let block = source
+        .block_by_height(block_height)
+        .await?
+        .ok_or(TraceError::NoSuchBlock)?;
+
+    let storage_reads = source.storage_read_replay(block_height).await?;
+
+    let consensus_params = source
+        .consensus_parameters(block.consensus_parameters_version)
+        .await?
+        .ok_or(TraceError::NoConsensusParameters)?;
+    let chain_id = consensus_params.chain_id();
+
+    let (mint_tx_id, tx_ids) = block
+        .transactions
+        .split_last()
+        .ok_or(TraceError::MalformedBlock)?;
+
+    // The transactions after the target won't be executed, so only the mint
+    // transaction is needed from them. Otherwise everything is fetched in one go.
+    let (txs, mint_tx) = match target {
+        Some(target) => {
+            let index = tx_ids
+                .iter()
+                .position(|tx_id| *tx_id == target)
+                .ok_or(TraceError::MissingTransaction(target))?;
+            let txs = source
+                .block_transactions(block_height, &chain_id, &tx_ids[..=index])
+                .await?;
+            (txs, block_transaction(source, mint_tx_id).await?)
+        }
+        None => {
+            let mut txs = source
+                .block_transactions(block_height, &chain_id, &block.transactions)
+                .await?;
+            let mint_tx = txs.pop().ok_or(TraceError::MalformedBlock)?;
+            (txs, mint_tx)
+        }
+    };
+    let TransactionType::Known(Transaction::Mint(mint_tx)) = mint_tx.transaction else {
+        return Err(TraceError::MalformedBlock);
+    };
+
+    let txs = tx_ids.iter().copied().zip(txs).collect();
+    Ok(BlockData {
+        gas_price: *mint_tx.gas_price(),
+        coinbase: mint_tx.input_contract().contract_id,
+        block,
+        storage_reads,
+        consensus_params,
+        txs,
+    })
+}
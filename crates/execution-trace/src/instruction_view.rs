@@ -0,0 +1,238 @@
+use fuel_vm::{
+    fuel_asm::Opcode,
+    prelude::{ContractId, DependentCost, GasCosts, Instruction, RegId, Word},
+};
+
+use crate::{TraceContext, VmView};
+
+/// Gas charged for an instruction under the current gas costs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionCost {
+    /// Always the same amount
+    Fixed(Word),
+    /// Depends on the amount of data processed, on top of a base cost
+    Dependent(DependentCost),
+    /// Not defined by the gas costs, e.g. `ECAL` whose cost is up to its handler
+    Unknown,
+}
+
+/// Decoded view of the instruction that's about to be executed
+#[derive(Debug, Clone)]
+pub struct InstructionView {
+    /// Value of `$pc`
+    pub pc: Word,
+    /// `None` if the memory at `$pc` isn't a valid instruction,
+    /// or on the step after the program returned, see [`TraceContext::finished`]
+    pub instruction: Option<Instruction>,
+    /// Contract whose code is being executed, `None` in the script or a predicate
+    pub contract_id: Option<ContractId>,
+    /// Register operands of the instruction with their current values
+    pub registers: Vec<(RegId, Word)>,
+    /// Gas charged for the instruction, `None` if there's no instruction
+    pub cost: Option<InstructionCost>,
+}
+
+impl InstructionView {
+    /// Decodes the instruction at `$pc` of the VM
+    pub fn new(vm: &dyn VmView, ctx: &TraceContext) -> Self {
+        // After the program returned, `$pc` points past its last instruction
+        let instruction = vm.instruction().filter(|_| !ctx.finished);
+        let registers = instruction
+            .map(|instruction| {
+                instruction
+                    .reg_ids()
+                    .into_iter()
+                    .flatten()
+                    .map(|reg| (reg, vm.registers()[reg]))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cost =
+            instruction.map(|instruction| instruction_cost(vm.gas_costs(), instruction.opcode()));
+
+        Self {
+            pc: vm.registers()[RegId::PC],
+            instruction,
+            contract_id: vm.contract_id(),
+            registers,
+            cost,
+        }
+    }
+}
+
+/// Mirrors the gas charged by each opcode in fuel-vm
+fn instruction_cost(gas_costs: &GasCosts, opcode: Opcode) -> InstructionCost {
+    match opcode {
+        Opcode::ADD => InstructionCost::Fixed(gas_costs.add()),
+        Opcode::ADDI => InstructionCost::Fixed(gas_costs.addi()),
+        Opcode::AND => InstructionCost::Fixed(gas_costs.and()),
+        Opcode::ANDI => InstructionCost::Fixed(gas_costs.andi()),
+        Opcode::DIV => InstructionCost::Fixed(gas_costs.div()),
+        Opcode::DIVI => InstructionCost::Fixed(gas_costs.divi()),
+        Opcode::EQ => InstructionCost::Fixed(gas_costs.eq_()),
+        Opcode::EXP => InstructionCost::Fixed(gas_costs.exp()),
+        Opcode::EXPI => InstructionCost::Fixed(gas_costs.expi()),
+        Opcode::GT => InstructionCost::Fixed(gas_costs.gt()),
+        Opcode::LT => InstructionCost::Fixed(gas_costs.lt()),
+        Opcode::WDCM => InstructionCost::Fixed(gas_costs.wdcm()),
+        Opcode::WQCM => InstructionCost::Fixed(gas_costs.wqcm()),
+        Opcode::WDOP => InstructionCost::Fixed(gas_costs.wdop()),
+        Opcode::WQOP => InstructionCost::Fixed(gas_costs.wqop()),
+        Opcode::WDML => InstructionCost::Fixed(gas_costs.wdml()),
+        Opcode::WQML => InstructionCost::Fixed(gas_costs.wqml()),
+        Opcode::WDDV => InstructionCost::Fixed(gas_costs.wddv()),
+        Opcode::WQDV => InstructionCost::Fixed(gas_costs.wqdv()),
+        Opcode::WDMD => InstructionCost::Fixed(gas_costs.wdmd()),
+        Opcode::WQMD => InstructionCost::Fixed(gas_costs.wqmd()),
+        Opcode::WDAM => InstructionCost::Fixed(gas_costs.wdam()),
+        Opcode::WQAM => InstructionCost::Fixed(gas_costs.wqam()),
+        Opcode::WDMM => InstructionCost::Fixed(gas_costs.wdmm()),
+        Opcode::WQMM => InstructionCost::Fixed(gas_costs.wqmm()),
+        Opcode::MLOG => InstructionCost::Fixed(gas_costs.mlog()),
+        Opcode::MOD => InstructionCost::Fixed(gas_costs.mod_op()),
+        Opcode::MODI => InstructionCost::Fixed(gas_costs.modi()),
+        Opcode::MOVE => InstructionCost::Fixed(gas_costs.move_op()),
+        Opcode::MOVI => InstructionCost::Fixed(gas_costs.movi()),
+        Opcode::MROO => InstructionCost::Fixed(gas_costs.mroo()),
+        Opcode::MUL => InstructionCost::Fixed(gas_costs.mul()),
+        Opcode::MULI => InstructionCost::Fixed(gas_costs.muli()),
+        Opcode::MLDV => InstructionCost::Fixed(gas_costs.mldv()),
+        Opcode::NOOP => InstructionCost::Fixed(gas_costs.noop()),
+        Opcode::NOT => InstructionCost::Fixed(gas_costs.not()),
+        Opcode::OR => InstructionCost::Fixed(gas_costs.or()),
+        Opcode::ORI => InstructionCost::Fixed(gas_costs.ori()),
+        Opcode::SLL => InstructionCost::Fixed(gas_costs.sll()),
+        Opcode::SLLI => InstructionCost::Fixed(gas_costs.slli()),
+        Opcode::SRL => InstructionCost::Fixed(gas_costs.srl()),
+        Opcode::SRLI => InstructionCost::Fixed(gas_costs.srli()),
+        Opcode::SUB => InstructionCost::Fixed(gas_costs.sub()),
+        Opcode::SUBI => InstructionCost::Fixed(gas_costs.subi()),
+        Opcode::XOR => InstructionCost::Fixed(gas_costs.xor()),
+        Opcode::XORI => InstructionCost::Fixed(gas_costs.xori()),
+        Opcode::JI => InstructionCost::Fixed(gas_costs.ji()),
+        Opcode::JNEI => InstructionCost::Fixed(gas_costs.jnei()),
+        Opcode::JNZI => InstructionCost::Fixed(gas_costs.jnzi()),
+        Opcode::JMP => InstructionCost::Fixed(gas_costs.jmp()),
+        Opcode::JNE => InstructionCost::Fixed(gas_costs.jne()),
+        Opcode::JMPF => InstructionCost::Fixed(gas_costs.jmpf()),
+        Opcode::JMPB => InstructionCost::Fixed(gas_costs.jmpb()),
+        Opcode::JNZF => InstructionCost::Fixed(gas_costs.jnzf()),
+        Opcode::JNZB => InstructionCost::Fixed(gas_costs.jnzb()),
+        Opcode::JNEF => InstructionCost::Fixed(gas_costs.jnef()),
+        Opcode::JNEB => InstructionCost::Fixed(gas_costs.jneb()),
+        Opcode::RET => InstructionCost::Fixed(gas_costs.ret()),
+        Opcode::RVRT => InstructionCost::Fixed(gas_costs.rvrt()),
+        Opcode::CFSI => InstructionCost::Fixed(gas_costs.cfsi()),
+        Opcode::CFS => InstructionCost::Fixed(gas_costs.cfsi()),
+        Opcode::PSHL => InstructionCost::Fixed(gas_costs.pshl()),
+        Opcode::PSHH => InstructionCost::Fixed(gas_costs.pshh()),
+        Opcode::POPL => InstructionCost::Fixed(gas_costs.popl()),
+        Opcode::POPH => InstructionCost::Fixed(gas_costs.poph()),
+        Opcode::LB => InstructionCost::Fixed(gas_costs.lb()),
+        Opcode::LW => InstructionCost::Fixed(gas_costs.lw()),
+        Opcode::SB => InstructionCost::Fixed(gas_costs.sb()),
+        Opcode::SW => InstructionCost::Fixed(gas_costs.sw()),
+        Opcode::BAL => InstructionCost::Fixed(gas_costs.bal()),
+        Opcode::BHEI => InstructionCost::Fixed(gas_costs.bhei()),
+        Opcode::BHSH => InstructionCost::Fixed(gas_costs.bhsh()),
+        Opcode::BURN => InstructionCost::Fixed(gas_costs.burn()),
+        Opcode::CB => InstructionCost::Fixed(gas_costs.cb()),
+        Opcode::LOG => InstructionCost::Fixed(gas_costs.log()),
+        Opcode::MINT => InstructionCost::Fixed(gas_costs.mint()),
+        Opcode::SRW => InstructionCost::Fixed(gas_costs.srw()),
+        Opcode::SWW => InstructionCost::Fixed(gas_costs.sww()),
+        Opcode::TIME => InstructionCost::Fixed(gas_costs.time()),
+        Opcode::ECK1 => InstructionCost::Fixed(gas_costs.eck1()),
+        Opcode::ECR1 => InstructionCost::Fixed(gas_costs.ecr1()),
+        Opcode::FLAG => InstructionCost::Fixed(gas_costs.flag()),
+        Opcode::GM => InstructionCost::Fixed(gas_costs.gm()),
+        Opcode::GTF => InstructionCost::Fixed(gas_costs.gtf()),
+        Opcode::TR => InstructionCost::Fixed(gas_costs.tr()),
+        Opcode::TRO => InstructionCost::Fixed(gas_costs.tro()),
+        Opcode::ECOP => gas_costs
+            .ecop()
+            .map_or(InstructionCost::Unknown, InstructionCost::Fixed),
+        Opcode::RETD => InstructionCost::Dependent(gas_costs.retd()),
+        Opcode::SMO => InstructionCost::Dependent(gas_costs.smo()),
+        Opcode::ALOC => InstructionCost::Dependent(gas_costs.aloc()),
+        Opcode::CFEI => InstructionCost::Dependent(gas_costs.cfei()),
+        Opcode::CFE => InstructionCost::Dependent(gas_costs.cfe()),
+        Opcode::MCL => InstructionCost::Dependent(gas_costs.mcl()),
+        Opcode::MCLI => InstructionCost::Dependent(gas_costs.mcli()),
+        Opcode::MCP => InstructionCost::Dependent(gas_costs.mcp()),
+        Opcode::MCPI => InstructionCost::Dependent(gas_costs.mcpi()),
+        Opcode::MEQ => InstructionCost::Dependent(gas_costs.meq()),
+        Opcode::CALL => InstructionCost::Dependent(gas_costs.call()),
+        Opcode::CCP => InstructionCost::Dependent(gas_costs.ccp()),
+        Opcode::CROO => InstructionCost::Dependent(gas_costs.croo()),
+        Opcode::CSIZ => InstructionCost::Dependent(gas_costs.csiz()),
+        Opcode::LDC => InstructionCost::Dependent(gas_costs.ldc()),
+        Opcode::LOGD => InstructionCost::Dependent(gas_costs.logd()),
+        Opcode::SCWQ => InstructionCost::Dependent(gas_costs.scwq()),
+        Opcode::SRWQ => InstructionCost::Dependent(gas_costs.srwq()),
+        Opcode::SWWQ => InstructionCost::Dependent(gas_costs.swwq()),
+        Opcode::ED19 => InstructionCost::Dependent(gas_costs.ed19()),
+        Opcode::K256 => InstructionCost::Dependent(gas_costs.k256()),
+        Opcode::S256 => InstructionCost::Dependent(gas_costs.s256()),
+        Opcode::BSIZ => gas_costs
+            .bsiz()
+            .map_or(InstructionCost::Unknown, InstructionCost::Dependent),
+        Opcode::BLDD => gas_costs
+            .bldd()
+            .map_or(InstructionCost::Unknown, InstructionCost::Dependent),
+        Opcode::EPAR => gas_costs
+            .epar()
+            .map_or(InstructionCost::Unknown, InstructionCost::Dependent),
+        // Charged by the handler
+        Opcode::ECAL => InstructionCost::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_costs_match_the_gas_costs() {
+        let gas_costs = GasCosts::default();
+        for (opcode, cost) in [
+            (Opcode::ADD, gas_costs.add()),
+            (Opcode::MOVI, gas_costs.movi()),
+            (Opcode::RET, gas_costs.ret()),
+            (Opcode::SWW, gas_costs.sww()),
+            (Opcode::TR, gas_costs.tr()),
+        ] {
+            assert_eq!(
+                instruction_cost(&gas_costs, opcode),
+                InstructionCost::Fixed(cost),
+                "{opcode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn dependent_costs_match_the_gas_costs() {
+        let gas_costs = GasCosts::default();
+        for (opcode, cost) in [
+            (Opcode::CALL, gas_costs.call()),
+            (Opcode::LOGD, gas_costs.logd()),
+            (Opcode::MCP, gas_costs.mcp()),
+            (Opcode::K256, gas_costs.k256()),
+            (Opcode::CCP, gas_costs.ccp()),
+        ] {
+            assert_eq!(
+                instruction_cost(&gas_costs, opcode),
+                InstructionCost::Dependent(cost),
+                "{opcode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ecal_cost_is_unknown() {
+        assert_eq!(
+            instruction_cost(&GasCosts::default(), Opcode::ECAL),
+            InstructionCost::Unknown
+        );
+    }
+}
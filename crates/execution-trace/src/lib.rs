@@ -9,27 +9,58 @@
     clippy::string_slice
 )]
 
+mod context;
+mod data_source;
+mod diagnostic;
+mod fetch;
+mod instruction_view;
 mod memory_reader;
+mod predicate;
+mod receipt_diff;
+mod replay;
 mod shallow_storage;
+mod snapshot;
+mod state_diff;
+mod storage_access;
+mod tracer;
+mod vm_view;
 
+pub use context::{ExecutionPhase, TraceContext};
+pub use data_source::{
+    BlockInfo, FileDataSource, MemoryDataSource, TraceDataSource, FIXTURE_VERSION,
+};
+pub use diagnostic::{GasUsage, ReplayPolicy, TraceDiagnostic};
+pub use instruction_view::{InstructionCost, InstructionView};
 pub use memory_reader::MemoryReader;
+pub use receipt_diff::{FieldDiff, ReceiptDiff};
+pub use shallow_storage::Error as StorageError;
+pub use snapshot::{StorageSnapshot, SNAPSHOT_VERSION};
+pub use state_diff::{BalanceChange, SlotChange, StateDiff};
+pub use storage_access::{StorageAccess, StorageAccessKind};
+pub use tracer::{BlockTracer, TraceFlow};
+pub use vm_view::VmView;
 
-use std::cell::RefCell;
-
-use field::{InputContract, MintGasPrice};
-use fuel_core_client::client::{
-    types::{TransactionStatus, TransactionType},
-    FuelClient,
+use std::{
+    cell::RefCell,
+    ops::{ControlFlow, RangeInclusive},
+    rc::Rc,
 };
+
+use fetch::{fetch_block, BlockData};
+use fuel_core_client::client::types::{TransactionStatus, TransactionType};
 use fuel_vm::{
-    checked_transaction::{CheckError, IntoChecked},
+    checked_transaction::CheckError,
     fuel_types::BlockHeight,
-    interpreter::{InterpreterParams, NotSupportedEcal},
+    interpreter::{EcalHandler, NotSupportedEcal},
     prelude::*,
 };
 
+use futures::{stream, StreamExt};
+use replay::BlockReplay;
 use shallow_storage::ShallowStorage;
+use storage_access::StorageJournal;
 use thiserror::Error;
+use tokio::sync::mpsc;
 
 #[derive(Error, Debug)]
 pub enum TraceError {
@@ -37,6 +68,10 @@ pub enum TraceError {
     Network(#[from] std::io::Error),
     #[error("Requested block doesn't exist")]
     NoSuchBlock,
+    #[error("Requested transaction doesn't exist")]
+    NoSuchTransaction(TxId),
+    #[error("Requested transaction is not included in a block")]
+    TransactionNotIncluded(TxId),
     #[error("Requested block is malformed")]
     MalformedBlock,
     #[error("Couldn't get consensus parameters for the block")]
@@ -47,66 +82,288 @@ pub enum TraceError {
     UnknownTransactionType(TxId),
     #[error("Transaction failed checking")]
     CheckTransaction(TxId, CheckError),
-    #[error("Local execution produced different receipts")]
-    ReceiptsMismatch(TxId, Vec<Receipt>),
+    #[error(
+        "Local execution of transaction {tx_id} produced different receipts, diverging at index {}",
+        diff.first_divergent_index
+    )]
+    ReceiptsMismatch { tx_id: TxId, diff: Box<ReceiptDiff> },
+    #[error("Local execution of transaction {tx_id} used {actual:?}, expected {expected:?}")]
+    GasMismatch {
+        tx_id: TxId,
+        expected: GasUsage,
+        actual: GasUsage,
+    },
+    #[error("Computing the gas or fee of transaction {tx_id} overflowed, expected {expected:?}")]
+    GasOverflow { tx_id: TxId, expected: GasUsage },
+    #[error("Interpreter failed to execute transaction {tx_id}: {error:?}")]
+    Interpreter {
+        tx_id: TxId,
+        /// Index of the traced instruction that failed, `None` if the transaction wasn't being single-stepped
+        instruction_index: Option<u64>,
+        error: InterpreterError<StorageError>,
+    },
+    #[error("Transaction {tx_id} uses functionality not supported by the tracer: {reason}")]
+    Unsupported { tx_id: TxId, reason: String },
+    #[error("Storage replay or snapshot is invalid: {0:?}")]
+    Storage(StorageError),
 }
 
 /// The VM type used for tracing
-pub type Vm = Interpreter<MemoryInstance, ShallowStorage, Script, NotSupportedEcal>;
+pub type Vm<Tx = Script, Ecal = NotSupportedEcal> =
+    Interpreter<MemoryInstance, ShallowStorage, Tx, Ecal>;
+
+/// Options controlling what gets traced
+#[derive(Debug, Clone)]
+pub struct TraceConfig<Ecal = NotSupportedEcal> {
+    /// Also single-step the predicates of all inputs, before executing the transaction itself
+    pub predicates: bool,
+    /// How many blocks [`trace_blocks`] fetches ahead of the one being executed
+    pub prefetch_blocks: usize,
+    /// What to do when a transaction's receipts, gas or fee differ from the chain
+    pub replay_policy: ReplayPolicy,
+    /// Drop the state changes of a transaction whose receipts diverged, instead of keeping
+    /// the locally computed ones. Neither is exact, as the correct state isn't known locally.
+    pub discard_divergent_state: bool,
+    /// Record each storage read and write of traced instructions and pass them to
+    /// [`BlockTracer::on_storage_access`]. Accesses done while a transaction isn't
+    /// single-stepped, e.g. after [`TraceFlow::SkipTransaction`], aren't reported.
+    pub storage_journal: bool,
+    /// Pass a [`StorageSnapshot`] of the block state before each transaction to
+    /// [`BlockTracer::on_snapshot`]. This copies the whole state for every transaction.
+    pub snapshots: bool,
+    /// Handler for the `ECAL` instruction, cloned for each executed transaction.
+    /// Predicates always use [`NotSupportedEcal`], as they can't use `ECAL`.
+    pub ecal: Ecal,
+}
+
+impl<Ecal> TraceConfig<Ecal> {
+    /// Replaces the `ECAL` handler, keeping the other options
+    pub fn with_ecal<NewEcal>(self, ecal: NewEcal) -> TraceConfig<NewEcal> {
+        TraceConfig {
+            predicates: self.predicates,
+            prefetch_blocks: self.prefetch_blocks,
+            replay_policy: self.replay_policy,
+            discard_divergent_state: self.discard_divergent_state,
+            storage_journal: self.storage_journal,
+            snapshots: self.snapshots,
+            ecal,
+        }
+    }
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            predicates: false,
+            prefetch_blocks: 4,
+            replay_policy: ReplayPolicy::Strict,
+            discard_divergent_state: false,
+            storage_journal: false,
+            snapshots: false,
+            ecal: NotSupportedEcal,
+        }
+    }
+}
 
-pub async fn trace_block<Callback>(
-    client: &FuelClient,
+/// Trace all transactions in the given block, passing each executed instruction to the tracer.
+/// Returns early without an error if the tracer stops the trace with [`TraceFlow::StopBlock`].
+pub async fn trace_block<D, T, Ecal>(
+    source: &D,
     block_height: BlockHeight,
-    mut on_instruction: Callback,
+    config: TraceConfig<Ecal>,
+    tracer: &mut T,
 ) -> Result<(), TraceError>
 where
-    Callback: FnMut(&Vm),
+    D: TraceDataSource + ?Sized,
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
 {
-    let block = client
-        .block_by_height(block_height)
+    trace_block_until(source, block_height, None, config, tracer).await
+}
+
+/// Trace a single transaction, passing each executed instruction to the tracer.
+/// The transactions preceding it in the same block are executed without tracing,
+/// so that the traced one sees the correct state.
+pub async fn trace_transaction<D, T, Ecal>(
+    source: &D,
+    tx_id: &TxId,
+    config: TraceConfig<Ecal>,
+    tracer: &mut T,
+) -> Result<(), TraceError>
+where
+    D: TraceDataSource + ?Sized,
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
+{
+    let tx = source
+        .transaction(tx_id)
         .await?
-        .ok_or(TraceError::NoSuchBlock)?;
+        .ok_or(TraceError::NoSuchTransaction(*tx_id))?;
+
+    let block_height = match tx.status {
+        TransactionStatus::Success { block_height, .. }
+        | TransactionStatus::Failure { block_height, .. } => block_height,
+        TransactionStatus::Submitted { .. }
+        | TransactionStatus::SqueezedOut { .. }
+        | TransactionStatus::PreconfirmationSuccess { .. }
+        | TransactionStatus::PreconfirmationFailure { .. } => {
+            return Err(TraceError::TransactionNotIncluded(*tx_id))
+        }
+    };
 
-    let storage_reads = client.storage_read_replay(&block_height).await?;
+    trace_block_until(source, block_height, Some(*tx_id), config, tracer).await
+}
 
-    let mint_tx_id = block
-        .transactions
-        .last()
-        .ok_or(TraceError::MalformedBlock)?;
-    let mint_tx = client
-        .transaction(&mint_tx_id)
-        .await?
-        .ok_or_else(|| TraceError::MissingTransaction((*mint_tx_id).into()))?;
-    let TransactionType::Known(Transaction::Mint(mint_tx)) = mint_tx.transaction else {
-        return Err(TraceError::MalformedBlock);
+/// Trace a transaction on top of a [`StorageSnapshot`], e.g. to see how a transaction that
+/// was never included would have executed at that point of the block. The transaction isn't
+/// on chain, so it isn't checked for divergences and `on_tx_end` gets a `Submitted` status.
+/// The block hooks are called around it as if it was the only transaction of the block.
+pub fn trace_snapshot_transaction<T, Ecal>(
+    snapshot: StorageSnapshot,
+    tx: Transaction,
+    config: TraceConfig<Ecal>,
+    tracer: &mut T,
+) -> Result<(), TraceError>
+where
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
+{
+    let block_height = snapshot.block_height;
+    let tx_id = tx.id(&snapshot.consensus_params.chain_id());
+    let tx_index = snapshot.tx_index;
+    let gas_price = snapshot.gas_price;
+    let consensus_params = snapshot.consensus_params.clone();
+    let status = TransactionStatus::Submitted {
+        submitted_at: snapshot.timestamp,
     };
 
-    let gas_price = *mint_tx.gas_price();
-    let coinbase = mint_tx.input_contract().contract_id;
+    let journal = config.storage_journal.then(StorageJournal::default);
+    let mut storage = ShallowStorage::try_from(snapshot).map_err(TraceError::Storage)?;
+    storage.journal = journal.clone();
 
-    let consensus_params = client
-        .consensus_parameters(block.header.consensus_parameters_version as i32)
-        .await?
-        .ok_or(TraceError::NoConsensusParameters)?;
+    let replay = BlockReplay {
+        block_height,
+        gas_price,
+        consensus_params: &consensus_params,
+        config: &config,
+    };
+
+    tracer.on_block_start(block_height);
+    let mut ctx = TraceContext::new(block_height, journal);
+    ctx.start_tx(tx_id, tx_index);
+    // Stopping early makes no difference for a single transaction
+    let _ = replay.replay_tx(&mut storage, &mut ctx, tx, &[], &status, true, tracer)?;
+    tracer.on_block_end(block_height);
+    Ok(())
+}
+
+/// Trace all transactions in a range of blocks, in order.
+/// While a block is executed, up to [`TraceConfig::prefetch_blocks`] upcoming blocks
+/// are fetched concurrently in the background, so this requires a multi-threaded tokio runtime
+/// to benefit from prefetching. Besides those, at most one fetched block waits to be executed.
+/// Returns early without an error if the tracer stops the trace with [`TraceFlow::StopBlock`].
+pub async fn trace_blocks<D, T, Ecal>(
+    source: &D,
+    heights: RangeInclusive<BlockHeight>,
+    config: TraceConfig<Ecal>,
+    tracer: &mut T,
+) -> Result<(), TraceError>
+where
+    D: TraceDataSource + Clone + Send + 'static,
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
+{
+    let prefetch = config.prefetch_blocks.max(1);
+    // The stream already buffers the blocks being fetched, the channel only hands them over
+    let (sender, mut receiver) = mpsc::channel(1);
+
+    let source = source.clone();
+    let (start, end) = (**heights.start(), **heights.end());
+    // The fetcher stops by itself once the receiver is dropped
+    tokio::spawn(async move {
+        let mut blocks = stream::iter(start..=end)
+            .map(|height| fetch_block(&source, BlockHeight::from(height), None))
+            .buffered(prefetch);
+        while let Some(block) = blocks.next().await {
+            let failed = block.is_err();
+            if sender.send(block).await.is_err() || failed {
+                break;
+            }
+        }
+    });
 
+    while let Some(block) = receiver.recv().await {
+        if execute_block(block?, None, &config, tracer)?.is_break() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes the transactions of a block in order. If `target` is given, only that transaction
+/// is traced, the ones before it are executed silently and the ones after it are skipped.
+async fn trace_block_until<D, T, Ecal>(
+    source: &D,
+    block_height: BlockHeight,
+    target: Option<TxId>,
+    config: TraceConfig<Ecal>,
+    tracer: &mut T,
+) -> Result<(), TraceError>
+where
+    D: TraceDataSource + ?Sized,
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
+{
+    let block = fetch_block(source, block_height, target).await?;
+    // Stopping early makes no difference for a single block
+    let _ = execute_block(block, target, &config, tracer)?;
+    Ok(())
+}
+
+/// Executes a fetched block, see [`trace_block_until`].
+/// Breaks if the tracer asked to stop tracing.
+fn execute_block<T, Ecal>(
+    block: BlockData,
+    target: Option<TxId>,
+    config: &TraceConfig<Ecal>,
+    tracer: &mut T,
+) -> Result<ControlFlow<()>, TraceError>
+where
+    T: BlockTracer + ?Sized,
+    Ecal: EcalHandler,
+{
+    let block_height = block.block.height;
+    let journal = config.storage_journal.then(StorageJournal::default);
     let mut storage = ShallowStorage {
         block_height,
-        timestamp: block.header.time,
-        consensus_parameters_version: block.header.consensus_parameters_version,
-        state_transition_version: block.header.state_transition_bytecode_version,
-        coinbase,
-        storage: RefCell::new(ShallowStorage::initial_storage(storage_reads)),
+        timestamp: block.block.time,
+        consensus_parameters_version: block.block.consensus_parameters_version,
+        state_transition_version: block.block.state_transition_bytecode_version,
+        coinbase: block.coinbase,
+        base: Rc::new(
+            ShallowStorage::initial_storage(block.storage_reads).map_err(TraceError::Storage)?,
+        ),
+        changes: RefCell::default(),
+        journal: journal.clone(),
     };
 
-    for tx_id in block.transactions.iter().take(block.transactions.len() - 1) {
-        let tx = client
-            .transaction(&tx_id)
-            .await?
-            .ok_or_else(|| TraceError::MissingTransaction((*tx_id).into()))?;
+    let replay = BlockReplay {
+        block_height,
+        gas_price: block.gas_price,
+        consensus_params: &block.consensus_params,
+        config,
+    };
+
+    tracer.on_block_start(block_height);
 
-        let receipts = match tx.status {
-            TransactionStatus::Success { receipts, .. } => receipts,
-            TransactionStatus::Failure { .. } => continue,
+    let mut flow = ControlFlow::Continue(());
+    let mut ctx = TraceContext::new(block_height, journal);
+    for (tx_index, (tx_id, tx)) in block.txs.into_iter().enumerate() {
+        let tx_status = tx.status;
+        let receipts = match &tx_status {
+            TransactionStatus::Success { receipts, .. }
+            | TransactionStatus::Failure { receipts, .. } => receipts,
             TransactionStatus::Submitted { .. }
             | TransactionStatus::SqueezedOut { .. }
             | TransactionStatus::PreconfirmationSuccess { .. }
@@ -116,53 +373,35 @@ where
         };
 
         let TransactionType::Known(tx) = tx.transaction else {
-            return Err(TraceError::UnknownTransactionType((*tx_id).into()));
-        };
-
-        let Transaction::Script(script_tx) = tx else {
-            continue;
+            return Err(TraceError::UnknownTransactionType(tx_id));
         };
 
-        let script_tx = script_tx
-            .into_checked_basic(block_height, &consensus_params)
-            .map_err(|err| TraceError::CheckTransaction((*tx_id).into(), err))?
-            .into_ready(
-                gas_price,
-                consensus_params.gas_costs(),
-                consensus_params.fee_params(),
-                Some(block_height),
-            )
-            .map_err(|err| TraceError::CheckTransaction((*tx_id).into(), err))?;
-
-        let mut vm = Interpreter::<_, _, Script>::with_storage(
-            MemoryInstance::new(),
-            storage.clone(),
-            InterpreterParams::new(gas_price, &consensus_params),
-        );
-        vm.set_single_stepping(true);
-
-        let mut t = *vm.transact(script_tx).expect("panicked").state();
-        loop {
-            on_instruction(&vm);
-            match t {
-                ProgramState::Return(_) | ProgramState::ReturnData(_) | ProgramState::Revert(_) => {
-                    break
-                }
-                ProgramState::RunProgram(_) | ProgramState::VerifyPredicate(_) => {
-                    t = vm.resume().expect("panicked");
-                }
-            }
+        let traced = target.is_none_or(|target| target == tx_id);
+        ctx.start_tx(tx_id, tx_index);
+        if config.snapshots {
+            let snapshot = StorageSnapshot::new(
+                &storage,
+                tx_index,
+                replay.gas_price,
+                replay.consensus_params,
+            );
+            tracer.on_snapshot(&ctx, &snapshot);
         }
 
-        if vm.receipts() != receipts {
-            return Err(TraceError::ReceiptsMismatch(
-                (*tx_id).into(),
-                vm.receipts().to_vec(),
-            ));
+        flow = replay.replay_tx(
+            &mut storage,
+            &mut ctx,
+            tx,
+            receipts,
+            &tx_status,
+            traced,
+            tracer,
+        )?;
+        if flow.is_break() {
+            break;
         }
-
-        storage = vm.as_ref().clone();
     }
 
-    Ok(())
+    tracer.on_block_end(block_height);
+    Ok(flow)
 }
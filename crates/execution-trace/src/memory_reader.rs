@@ -19,10 +19,14 @@ impl<'a> MemoryReader<'a> {
 
 impl Read for MemoryReader<'_> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let at = self.at.replace_with(|at| *at + buf.len() as Word);
-        buf.copy_from_slice(self.mem.read(at, buf.len()).map_err(|_err| {
-            std::io::Error::new(std::io::ErrorKind::Other, "Inaccessible memory")
-        })?);
+        let at = self
+            .at
+            .replace_with(|at| at.saturating_add(buf.len() as Word));
+        buf.copy_from_slice(
+            self.mem
+                .read(at, buf.len())
+                .map_err(|_err| std::io::Error::other("Inaccessible memory"))?,
+        );
         Ok(buf.len())
     }
 }
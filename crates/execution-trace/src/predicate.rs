@@ -0,0 +1,84 @@
+use fuel_vm::{
+    checked_transaction::CheckPredicateParams, interpreter::InterpreterParams, prelude::*,
+    state::ExecuteState,
+};
+
+use crate::{
+    replay::catch_vm_panic, shallow_storage::ShallowStorage, BlockTracer, ExecutionPhase,
+    TraceContext, TraceError, TraceFlow, Vm,
+};
+
+/// Single-steps all predicates of the transaction, passing each instruction to the tracer.
+/// Predicates are executed with zero gas price, like fuel-vm does when checking them.
+/// Returns early if the tracer asks to skip the transaction or stop the block.
+pub(crate) fn trace_predicates<Tx, T>(
+    ctx: &mut TraceContext,
+    tx: &Tx,
+    storage: &ShallowStorage,
+    consensus_params: &ConsensusParameters,
+    tracer: &mut T,
+) -> Result<TraceFlow, TraceError>
+where
+    Tx: ExecutableTransaction,
+    T: BlockTracer + ?Sized,
+{
+    let params = CheckPredicateParams::from(consensus_params);
+
+    for (input_index, input) in tx.inputs().iter().enumerate() {
+        let Some(predicate) = RuntimePredicate::from_tx(tx, params.tx_offset, input_index) else {
+            continue;
+        };
+        let (Some(owner), Some(gas_limit)) = (input.input_owner(), input.predicate_gas_used())
+        else {
+            continue;
+        };
+
+        let mut vm: Vm<Tx> = Interpreter::with_storage(
+            MemoryInstance::new(),
+            storage.clone(),
+            InterpreterParams::new(0, params.clone()),
+        );
+        catch_vm_panic(ctx.tx_id, || {
+            vm.init_predicate(
+                Context::PredicateVerification { program: predicate },
+                tx.clone(),
+                gas_limit,
+            )
+        })?
+        .map_err(|error| TraceError::Interpreter {
+            tx_id: ctx.tx_id,
+            instruction_index: None,
+            error,
+        })?;
+
+        ctx.start_phase(ExecutionPhase::Predicate {
+            input_index,
+            owner: *owner,
+        });
+        let mut finished = false;
+        loop {
+            let flow = ctx.step(&vm, tracer, finished);
+            if flow != TraceFlow::Continue {
+                return Ok(flow);
+            }
+            if finished {
+                break;
+            }
+            // The outcome of the predicate is already known from the transaction status,
+            // so any terminating state just ends the tracing of this predicate.
+            match catch_vm_panic(ctx.tx_id, || vm.execute::<true>())? {
+                Ok(ExecuteState::Proceed) => {}
+                Ok(_) => finished = true,
+                Err(error) => {
+                    return Err(TraceError::Interpreter {
+                        tx_id: ctx.tx_id,
+                        instruction_index: ctx.last_instruction_index(),
+                        error,
+                    })
+                }
+            }
+        }
+    }
+
+    Ok(TraceFlow::Continue)
+}
@@ -0,0 +1,161 @@
+use fuel_vm::prelude::Receipt;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Differences between the receipts a transaction produced on chain and in local replay
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptDiff {
+    /// Receipts from the transaction status
+    pub expected: Vec<Receipt>,
+    /// Receipts produced by the local execution
+    pub actual: Vec<Receipt>,
+    /// Index of the first receipt that differs or is present on only one side
+    pub first_divergent_index: usize,
+    /// Differing fields of the first divergent receipt.
+    /// Empty if the receipt is present on only one side.
+    pub field_diffs: Vec<FieldDiff>,
+}
+
+/// A single differing field of a receipt, with values rendered as JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldDiff {
+    /// Name of the field, `kind` if the receipts are of different types
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl ReceiptDiff {
+    /// Compares the receipts, returning `None` if they are equal
+    pub fn new(expected: &[Receipt], actual: &[Receipt]) -> Option<Self> {
+        let first_divergent_index = expected
+            .iter()
+            .zip(actual)
+            .position(|(e, a)| e != a)
+            .or_else(|| {
+                (expected.len() != actual.len()).then(|| expected.len().min(actual.len()))
+            })?;
+
+        let field_diffs = match (
+            expected.get(first_divergent_index),
+            actual.get(first_divergent_index),
+        ) {
+            (Some(expected), Some(actual)) => field_diffs(expected, actual),
+            _ => Vec::new(),
+        };
+
+        Some(Self {
+            expected: expected.to_vec(),
+            actual: actual.to_vec(),
+            first_divergent_index,
+            field_diffs,
+        })
+    }
+}
+
+/// Compares receipts field by field using their serialized form,
+/// which is an object keyed by the receipt kind holding an object of fields
+fn field_diffs(expected: &Receipt, actual: &Receipt) -> Vec<FieldDiff> {
+    let as_fields = |receipt: &Receipt| match serde_json::to_value(receipt) {
+        Ok(Value::Object(kind)) => kind.into_iter().next(),
+        _ => None,
+    };
+    let (Some((expected_kind, expected)), Some((actual_kind, actual))) =
+        (as_fields(expected), as_fields(actual))
+    else {
+        return Vec::new();
+    };
+
+    if expected_kind != actual_kind {
+        return vec![FieldDiff {
+            field: "kind".to_string(),
+            expected: expected_kind,
+            actual: actual_kind,
+        }];
+    }
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .filter_map(|(field, expected)| {
+                let actual = actual.get(field).unwrap_or(&Value::Null);
+                (expected != actual).then(|| FieldDiff {
+                    field: field.clone(),
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                })
+            })
+            .collect(),
+        (expected, actual) => vec![FieldDiff {
+            field: expected_kind,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuel_vm::prelude::{ContractId, ScriptExecutionResult};
+
+    fn ret(val: u64, pc: u64) -> Receipt {
+        Receipt::ret(ContractId::zeroed(), val, pc, 0)
+    }
+
+    fn script_result(gas_used: u64) -> Receipt {
+        Receipt::script_result(ScriptExecutionResult::Success, gas_used)
+    }
+
+    fn field(name: &str, expected: &str, actual: &str) -> FieldDiff {
+        FieldDiff {
+            field: name.to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+
+    #[test]
+    fn equal_receipts_have_no_diff() {
+        let receipts = [ret(1, 10), script_result(100)];
+        assert!(ReceiptDiff::new(&receipts, &receipts).is_none());
+        assert!(ReceiptDiff::new(&[], &[]).is_none());
+    }
+
+    #[test]
+    fn missing_receipts_diverge_at_the_shorter_length() {
+        let expected = [ret(1, 10), script_result(100)];
+
+        let diff = ReceiptDiff::new(&expected, &expected[..1]).unwrap();
+        assert_eq!(diff.first_divergent_index, 1);
+        assert!(diff.field_diffs.is_empty());
+
+        let diff = ReceiptDiff::new(&[], &expected).unwrap();
+        assert_eq!(diff.first_divergent_index, 0);
+        assert!(diff.field_diffs.is_empty());
+    }
+
+    #[test]
+    fn different_receipt_kinds_are_reported_as_kind() {
+        let diff = ReceiptDiff::new(&[ret(1, 10)], &[script_result(100)]).unwrap();
+        assert_eq!(diff.first_divergent_index, 0);
+        assert_eq!(diff.field_diffs, [field("kind", "Return", "ScriptResult")]);
+    }
+
+    #[test]
+    fn differing_fields_are_reported() {
+        let diff = ReceiptDiff::new(
+            &[ret(1, 10), script_result(100)],
+            &[ret(1, 10), script_result(120)],
+        )
+        .unwrap();
+        assert_eq!(diff.first_divergent_index, 1);
+        assert_eq!(diff.field_diffs, [field("gas_used", "100", "120")]);
+
+        let diff = ReceiptDiff::new(&[ret(1, 10)], &[ret(2, 12)]).unwrap();
+        assert_eq!(
+            diff.field_diffs,
+            [field("pc", "10", "12"), field("val", "1", "2")]
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use fuel_core_client::client::types::TransactionStatus;
+use std::{
+    any::Any,
+    ops::ControlFlow,
+    panic::{self, AssertUnwindSafe},
+};
+
+use fuel_vm::{
+    checked_transaction::{IntoChecked, Ready},
+    fuel_tx::field::MaxFeeLimit,
+    fuel_types::BlockHeight,
+    interpreter::{EcalHandler, InterpreterParams},
+    prelude::*,
+};
+
+use crate::{
+    predicate::trace_predicates, shallow_storage::ShallowStorage, BlockTracer, ExecutionPhase,
+    GasUsage, ReceiptDiff, ReplayPolicy, StateDiff, StorageError, TraceConfig, TraceContext,
+    TraceDiagnostic, TraceError, TraceFlow, Vm,
+};
+
+/// Block-level parameters needed to execute the transactions of a block
+pub(crate) struct BlockReplay<'a, Ecal> {
+    pub block_height: BlockHeight,
+    pub gas_price: Word,
+    pub consensus_params: &'a ConsensusParameters,
+    pub config: &'a TraceConfig<Ecal>,
+}
+
+impl<Ecal: EcalHandler> BlockReplay<'_, Ecal> {
+    /// Executes a single transaction on top of `storage`, applying its state effects.
+    /// If `traced` is set, the transaction is single-stepped and passed to the tracer.
+    /// Breaks if the tracer asked to stop tracing the block.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replay_tx<T>(
+        &self,
+        storage: &mut ShallowStorage,
+        ctx: &mut TraceContext,
+        tx: Transaction,
+        receipts: &[Receipt],
+        status: &TransactionStatus,
+        traced: bool,
+        tracer: &mut T,
+    ) -> Result<ControlFlow<()>, TraceError>
+    where
+        T: BlockTracer + ?Sized,
+    {
+        if traced {
+            tracer.on_tx_start(ctx);
+        }
+        ctx.record_storage(traced);
+        let result = self.execute_tx(storage, ctx, tx, receipts, status, traced, tracer);
+        ctx.record_storage(false);
+        let ControlFlow::Continue((local_receipts, state_diff)) = result? else {
+            return Ok(ControlFlow::Break(()));
+        };
+        if traced {
+            tracer.on_state_diff(ctx, &state_diff);
+            tracer.on_tx_end(ctx, &local_receipts, status);
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Executes the transaction and checks that it produces the expected receipts, gas and fee.
+    /// Returns the local receipts and the applied state changes.
+    /// Breaks without finishing the transaction if the tracer asked to stop tracing the block.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_tx<T>(
+        &self,
+        storage: &mut ShallowStorage,
+        ctx: &mut TraceContext,
+        tx: Transaction,
+        receipts: &[Receipt],
+        status: &TransactionStatus,
+        traced: bool,
+        tracer: &mut T,
+    ) -> Result<ControlFlow<(), (Vec<Receipt>, StateDiff)>, TraceError>
+    where
+        T: BlockTracer + ?Sized,
+    {
+        let mut flow = TraceFlow::Continue;
+        if traced && self.config.predicates {
+            let params = self.consensus_params;
+            flow = match &tx {
+                Transaction::Script(tx) => trace_predicates(ctx, tx, storage, params, tracer)?,
+                Transaction::Create(tx) => trace_predicates(ctx, tx, storage, params, tracer)?,
+                Transaction::Upgrade(tx) => trace_predicates(ctx, tx, storage, params, tracer)?,
+                Transaction::Upload(tx) => trace_predicates(ctx, tx, storage, params, tracer)?,
+                Transaction::Blob(tx) => trace_predicates(ctx, tx, storage, params, tracer)?,
+                Transaction::Mint(_) => TraceFlow::Continue,
+            };
+        }
+        if flow == TraceFlow::StopBlock {
+            return Ok(ControlFlow::Break(()));
+        }
+
+        let tx_id = ctx.tx_id;
+        let script_tx = match tx {
+            Transaction::Script(tx) => tx,
+            Transaction::Create(tx) => {
+                return self.execute_without_script(storage, ctx, tracer, tx, status, |vm, tx| {
+                    vm.deploy(tx).map(drop)
+                })
+            }
+            Transaction::Upgrade(tx) => {
+                return self.execute_without_script(storage, ctx, tracer, tx, status, |vm, tx| {
+                    vm.upgrade(tx).map(drop)
+                })
+            }
+            Transaction::Upload(tx) => {
+                return self.execute_without_script(storage, ctx, tracer, tx, status, |vm, tx| {
+                    vm.upload(tx).map(drop)
+                })
+            }
+            Transaction::Blob(tx) => {
+                return self.execute_without_script(storage, ctx, tracer, tx, status, |vm, tx| {
+                    vm.blob(tx).map(drop)
+                })
+            }
+            Transaction::Mint(_) => {
+                return Ok(ControlFlow::Continue((Vec::new(), StateDiff::default())))
+            }
+        };
+
+        let mut stepping = traced && flow == TraceFlow::Continue;
+        ctx.record_storage(stepping);
+        let mut vm = self.vm(storage);
+        let ready = self.check_tx(script_tx.clone(), tx_id)?;
+        vm.set_single_stepping(stepping);
+
+        let mut t = *catch_vm_panic(tx_id, || vm.transact(ready))?
+            .map_err(|error| TraceError::Interpreter {
+                tx_id,
+                instruction_index: None,
+                error,
+            })?
+            .state();
+        if stepping {
+            ctx.start_phase(ExecutionPhase::Script);
+        }
+        loop {
+            let finished = matches!(
+                t,
+                ProgramState::Return(_) | ProgramState::ReturnData(_) | ProgramState::Revert(_)
+            );
+            if stepping {
+                match ctx.step(&vm, tracer, finished) {
+                    TraceFlow::Continue => {}
+                    TraceFlow::SkipTransaction => {
+                        // Without single-stepping, the next resume runs the script to completion
+                        vm.set_single_stepping(false);
+                        ctx.record_storage(false);
+                        stepping = false;
+                    }
+                    TraceFlow::StopBlock => return Ok(ControlFlow::Break(())),
+                }
+            }
+            if finished {
+                break;
+            }
+            t = catch_vm_panic(tx_id, || vm.resume())?.map_err(|error| {
+                TraceError::Interpreter {
+                    tx_id,
+                    instruction_index: stepping.then(|| ctx.last_instruction_index()).flatten(),
+                    error,
+                }
+            })?;
+        }
+
+        // Transactions that aren't on chain have no receipts to compare with
+        let included = matches!(
+            status,
+            TransactionStatus::Success { .. } | TransactionStatus::Failure { .. }
+        );
+        let diff = included
+            .then(|| ReceiptDiff::new(receipts, vm.receipts()))
+            .flatten();
+        let diverged = diff.is_some();
+        if let Some(diff) = diff {
+            self.report_divergence(
+                ctx,
+                tracer,
+                TraceDiagnostic::ReceiptsMismatch(Box::new(diff)),
+            )?;
+        }
+        // The interpreter zeroes the predicate gas of its copy, which the minimal gas includes
+        self.check_gas(ctx, tracer, &script_tx, vm.receipts(), status)?;
+        let discard = diverged && self.config.discard_divergent_state;
+
+        // The VM doesn't roll back storage changes of a reverted script,
+        // fuel-core discards them instead, so we only keep them on success.
+        let local_receipts = vm.receipts().to_vec();
+        let state_diff = if !discard && !matches!(t, ProgramState::Revert(_)) {
+            commit(storage, vm)?
+        } else {
+            StateDiff::default()
+        };
+
+        Ok(ControlFlow::Continue((local_receipts, state_diff)))
+    }
+
+    /// Executes a transaction without a script. These are never traced, but their state effects
+    /// must be applied so that later transactions in the block see them.
+    fn execute_without_script<Tx, T>(
+        &self,
+        storage: &mut ShallowStorage,
+        ctx: &TraceContext,
+        tracer: &mut T,
+        tx: Tx,
+        status: &TransactionStatus,
+        execute: impl FnOnce(
+            &mut Vm<Script, Ecal>,
+            Ready<Tx>,
+        ) -> Result<(), InterpreterError<StorageError>>,
+    ) -> Result<ControlFlow<(), (Vec<Receipt>, StateDiff)>, TraceError>
+    where
+        Tx: IntoChecked + Chargeable,
+        T: BlockTracer + ?Sized,
+    {
+        ctx.record_storage(false);
+        self.check_gas(ctx, tracer, &tx, &[], status)?;
+        let tx = self.check_tx(tx, ctx.tx_id)?;
+        let mut vm = self.vm(storage);
+        catch_vm_panic(ctx.tx_id, || execute(&mut vm, tx))?.map_err(|error| {
+            TraceError::Interpreter {
+                tx_id: ctx.tx_id,
+                instruction_index: None,
+                error,
+            }
+        })?;
+        Ok(ControlFlow::Continue((Vec::new(), commit(storage, vm)?)))
+    }
+
+    /// Creates an interpreter on top of the current block state
+    fn vm(&self, storage: &ShallowStorage) -> Vm<Script, Ecal> {
+        Interpreter::with_storage_and_ecal(
+            MemoryInstance::new(),
+            storage.clone(),
+            InterpreterParams::new(self.gas_price, self.consensus_params),
+            self.config.ecal.clone(),
+        )
+    }
+
+    /// Compares the gas and fee of the locally executed transaction with its status
+    fn check_gas<Tx, T>(
+        &self,
+        ctx: &TraceContext,
+        tracer: &mut T,
+        tx: &Tx,
+        receipts: &[Receipt],
+        status: &TransactionStatus,
+    ) -> Result<(), TraceError>
+    where
+        Tx: Chargeable,
+        T: BlockTracer + ?Sized,
+    {
+        let expected = match *status {
+            TransactionStatus::Success {
+                total_gas,
+                total_fee,
+                ..
+            }
+            | TransactionStatus::Failure {
+                total_gas,
+                total_fee,
+                ..
+            } => GasUsage {
+                total_gas,
+                total_fee,
+            },
+            _ => return Ok(()),
+        };
+        let diagnostic = match self.gas_usage(tx, receipts) {
+            Some(actual) if actual == expected => return Ok(()),
+            Some(actual) => TraceDiagnostic::GasMismatch { expected, actual },
+            None => TraceDiagnostic::GasOverflow { expected },
+        };
+        self.report_divergence(ctx, tracer, diagnostic)
+    }
+
+    /// Computes gas and fee the same way fuel-core does: the minimal gas of the transaction
+    /// plus the gas used by its script, and the max fee minus the refund for unused gas.
+    fn gas_usage<Tx: Chargeable>(&self, tx: &Tx, receipts: &[Receipt]) -> Option<GasUsage> {
+        let gas_costs = self.consensus_params.gas_costs();
+        let fee_params = self.consensus_params.fee_params();
+        let used_gas = receipts
+            .iter()
+            .find_map(|receipt| match receipt {
+                Receipt::ScriptResult { gas_used, .. } => Some(*gas_used),
+                _ => None,
+            })
+            .unwrap_or(0);
+        let refund = tx.refund_fee(gas_costs, fee_params, used_gas, self.gas_price)?;
+        Some(GasUsage {
+            total_gas: tx.min_gas(gas_costs, fee_params).checked_add(used_gas)?,
+            total_fee: tx.max_fee_limit().checked_sub(refund)?,
+        })
+    }
+
+    /// Handles a divergence from the chain according to the replay policy
+    fn report_divergence<T>(
+        &self,
+        ctx: &TraceContext,
+        tracer: &mut T,
+        diagnostic: TraceDiagnostic,
+    ) -> Result<(), TraceError>
+    where
+        T: BlockTracer + ?Sized,
+    {
+        match self.config.replay_policy {
+            ReplayPolicy::Strict => Err(diagnostic.into_error(ctx.tx_id)),
+            ReplayPolicy::Warn => {
+                tracing::warn!("Replay of transaction {} diverged: {diagnostic}", ctx.tx_id);
+                tracer.on_diagnostic(ctx, &diagnostic);
+                Ok(())
+            }
+            ReplayPolicy::Ignore => Ok(()),
+        }
+    }
+
+    /// Checks the transaction against the consensus parameters and prepares it for execution
+    fn check_tx<Tx>(&self, tx: Tx, tx_id: TxId) -> Result<Ready<Tx>, TraceError>
+    where
+        Tx: IntoChecked + Chargeable,
+    {
+        tx.into_checked_basic(self.block_height, self.consensus_params)
+            .map_err(|err| TraceError::CheckTransaction(tx_id, err))?
+            .into_ready(
+                self.gas_price,
+                self.consensus_params.gas_costs(),
+                self.consensus_params.fee_params(),
+                Some(self.block_height),
+            )
+            .map_err(|err| TraceError::CheckTransaction(tx_id, err))
+    }
+}
+
+/// Applies the writes of an executed transaction to the block state, returning what changed
+fn commit<Tx, Ecal>(
+    storage: &mut ShallowStorage,
+    vm: Vm<Tx, Ecal>,
+) -> Result<StateDiff, TraceError> {
+    let changes = vm.as_ref().take_changes();
+    // The VM shares the base state, so it must be gone before committing to avoid copying it
+    drop(vm);
+    let state_diff = StateDiff::new(storage, &changes).map_err(TraceError::Storage)?;
+    storage.commit(changes);
+    Ok(state_diff)
+}
+
+/// Runs interpreter code, reporting its panics, e.g. on storage contents it doesn't expect,
+/// as an unsupported transaction instead of taking down the caller.
+/// Tracer callbacks are never run inside, so that their panics reach the caller unchanged.
+pub(crate) fn catch_vm_panic<R>(tx_id: TxId, f: impl FnOnce() -> R) -> Result<R, TraceError> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| TraceError::Unsupported {
+        tx_id,
+        reason: panic_message(payload),
+    })
+}
+
+/// Extracts the message from a panic payload
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
@@ -1,21 +1,32 @@
-use fuel_core_storage::column::Column;
-use fuel_core_types::{services::executor::StorageReadReplayEvent, tai64::Tai64};
+use fuel_core_storage::{
+    codec::{postcard::Postcard, Decode, Encode, Encoder},
+    column::Column,
+};
+use fuel_core_types::{
+    blockchain::block::CompressedBlock, services::executor::StorageReadReplayEvent, tai64::Tai64,
+};
 use fuel_vm::{
     error::{InterpreterError, RuntimeError},
     fuel_storage::{StorageRead, StorageSize, StorageWrite},
     fuel_types::BlockHeight,
     prelude::*,
     storage::{
-        BlobData, ContractsAssetKey, ContractsAssets, ContractsAssetsStorage, ContractsRawCode,
-        ContractsState, ContractsStateData, ContractsStateKey, InterpreterStorage,
-        UploadedBytecodes,
+        BlobBytes, BlobData, ContractsAssetKey, ContractsAssets, ContractsAssetsStorage,
+        ContractsRawCode, ContractsState, ContractsStateData, ContractsStateKey,
+        InterpreterStorage, UploadedBytecode, UploadedBytecodes,
     },
 };
 use primitive_types::U256;
-use std::{cell::RefCell, collections::HashMap};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::storage_access::{StorageAccess, StorageAccessKind, StorageJournal};
 
-type InnerStorage = HashMap<Column, HashMap<Vec<u8>, Option<Vec<u8>>>>;
+pub(crate) type InnerStorage = HashMap<Column, HashMap<Vec<u8>, Option<Vec<u8>>>>;
 
+/// Storage built from the reads fuel-core did while executing the block.
+/// Writes go to a separate layer, so that the state of the block can be shared
+/// between interpreters cheaply, and the writes of a transaction can be inspected,
+/// committed or discarded as a whole.
 #[derive(Clone)]
 pub struct ShallowStorage {
     pub block_height: BlockHeight,
@@ -23,37 +34,181 @@ pub struct ShallowStorage {
     pub consensus_parameters_version: u32,
     pub state_transition_version: u32,
     pub coinbase: fuel_vm::prelude::ContractId,
-    pub storage: RefCell<InnerStorage>,
+    /// State before the current transaction
+    pub base: Rc<InnerStorage>,
+    /// Writes done by the current transaction, `None` values are removals
+    pub changes: RefCell<InnerStorage>,
+    /// Where reads and writes are recorded, if enabled
+    pub(crate) journal: Option<StorageJournal>,
 }
 
 impl ShallowStorage {
-    pub fn initial_storage(reads: Vec<StorageReadReplayEvent>) -> InnerStorage {
+    pub fn initial_storage(reads: Vec<StorageReadReplayEvent>) -> Result<InnerStorage, Error> {
         let mut storage: InnerStorage = HashMap::new();
         for read in reads {
-            let column = Column::try_from(read.column).expect("Invalid column id in read event");
+            let column =
+                Column::try_from(read.column).map_err(|_| Error::InvalidColumn(read.column))?;
             storage
                 .entry(column)
                 .or_default()
                 .insert(read.key, read.value);
         }
-        storage
+        Ok(storage)
+    }
+
+    /// Takes the writes of the current transaction, leaving the base state as it was
+    pub fn take_changes(&self) -> InnerStorage {
+        self.changes.take()
+    }
+
+    /// Applies writes to the base state. This only copies the base state
+    /// if it's still shared, so interpreters using it should be dropped first.
+    pub fn commit(&mut self, changes: InnerStorage) {
+        let base = Rc::make_mut(&mut self.base);
+        for (column, values) in changes {
+            base.entry(column).or_default().extend(values);
+        }
+    }
+
+    fn record(
+        &self,
+        column: Column,
+        key: Vec<u8>,
+        kind: StorageAccessKind,
+        old_value: &Option<Vec<u8>>,
+        new_value: &Option<Vec<u8>>,
+    ) {
+        if let Some(journal) = self
+            .journal
+            .as_ref()
+            .filter(|journal| journal.is_recording())
+        {
+            journal.push(StorageAccess {
+                column,
+                key,
+                kind,
+                old_value: old_value.clone(),
+                new_value: new_value.clone(),
+                instruction_index: None,
+                contract_id: None,
+            });
+        }
+    }
+
+    /// Reads a value, failing if the key wasn't read by fuel-core or written during the replay,
+    /// as its value isn't known then
+    fn value_of_column(&self, column: Column, key: Vec<u8>) -> Result<Option<Vec<u8>>, Error> {
+        let Some(value) = self.entry(column, &key) else {
+            return Err(Error::MissingReplayKey { column, key });
+        };
+        self.record(column, key, StorageAccessKind::Read, &value, &value);
+        Ok(value)
+    }
+
+    /// Value of the key, treating keys not covered by the replay as absent
+    pub(crate) fn lookup(&self, column: Column, key: &[u8]) -> Option<Vec<u8>> {
+        self.entry(column, key).flatten()
+    }
+
+    /// Value of the key, or `None` if the key isn't covered by the replay
+    fn entry(&self, column: Column, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        if let Some(value) = self
+            .changes
+            .borrow()
+            .get(&column)
+            .and_then(|values| values.get(key))
+        {
+            return Some(value.clone());
+        }
+        self.base.get(&column)?.get(key).cloned()
     }
 
-    fn value_of_column(&self, column: Column, key: Vec<u8>) -> Option<Vec<u8>> {
-        self.storage.borrow().get(&column)?.get(&key)?.clone()
+    /// Header of a past block, as read by fuel-core when the block used `TIME` or `BHSH` on it
+    fn past_block(&self, height: BlockHeight) -> Result<CompressedBlock, Error> {
+        let key = height.to_bytes().to_vec();
+        let data = self
+            .value_of_column(Column::FuelBlocks, key.clone())?
+            .ok_or(Error::MissingBlockHeader(height))?;
+        Postcard::decode(&data).map_err(|_| Error::InvalidValue {
+            column: Column::FuelBlocks,
+            key,
+        })
+    }
+
+    /// Copies the value from `offset` into `buf` the way fuel-vm's `MemoryStorage` does:
+    /// contract state is zero-filled past its end, other values must fill the whole buffer
+    fn copy_value(
+        column: Column,
+        value: &[u8],
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<(), Error> {
+        let rest = value.get(offset..).ok_or(Error::CannotRead)?;
+        if column == Column::ContractsState {
+            let len = buf.len().min(rest.len());
+            let (head, tail) = buf.split_at_mut(len);
+            head.copy_from_slice(&rest[..len]);
+            tail.fill(0);
+        } else {
+            buf.copy_from_slice(rest.get(..buf.len()).ok_or(Error::CannotRead)?);
+        }
+        Ok(())
     }
 
+    /// Decodes a value of the column, failing if `convert` can't
+    fn decode<T>(
+        column: Column,
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+        convert: impl FnOnce(Vec<u8>) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        value
+            .map(|data| convert(data).ok_or(Error::InvalidValue { column, key }))
+            .transpose()
+    }
+
+    /// Writes a value, returning the previous one. Fails if the key isn't covered by the replay,
+    /// as the previous value isn't known then.
     fn replace_column(
         &self,
         column: Column,
         key: Vec<u8>,
         value: Option<Vec<u8>>,
-    ) -> Option<Vec<u8>> {
-        self.storage
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some(previous) = self.entry(column, &key) else {
+            return Err(Error::MissingReplayKey { column, key });
+        };
+        self.write(column, key, previous.clone(), value);
+        Ok(previous)
+    }
+
+    /// Writes a value, discarding the previous one
+    fn write_column(&self, column: Column, key: Vec<u8>, value: Option<Vec<u8>>) {
+        // The previous value isn't always read by fuel-core when writing,
+        // so writes to keys not covered by the replay don't fail
+        let previous = self.lookup(column, &key);
+        self.write(column, key, previous, value);
+    }
+
+    fn write(
+        &self,
+        column: Column,
+        key: Vec<u8>,
+        previous: Option<Vec<u8>>,
+        value: Option<Vec<u8>>,
+    ) {
+        self.record(
+            column,
+            key.clone(),
+            StorageAccessKind::Write,
+            &previous,
+            &value,
+        );
+        self.changes
             .borrow_mut()
             .entry(column)
             .or_default()
-            .insert(key.clone(), value)?
+            .insert(key, value);
     }
 }
 
@@ -77,7 +232,7 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
                 );
-                let head = self.value_of_column(Column::$core_column, $convert_key(key));
+                let head = self.value_of_column(Column::$core_column, $convert_key(key))?;
                 Ok(head.map(|v| v.len()))
             }
         }
@@ -89,7 +244,9 @@ macro_rules! storage_rw {
                 &self,
                 key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
             ) -> Result<
-                Option<std::borrow::Cow<<$vm_type as fuel_vm::fuel_storage::Mappable>::OwnedValue>>,
+                Option<
+                    std::borrow::Cow<'_, <$vm_type as fuel_vm::fuel_storage::Mappable>::OwnedValue>,
+                >,
                 Self::Error,
             > {
                 tracing::debug!(
@@ -97,8 +254,12 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
                 );
-                let head = self.value_of_column(Column::$core_column, $convert_key(key));
-                Ok(head.map($convert_value).map(std::borrow::Cow::Owned))
+                let key = $convert_key(key);
+                let head = self.value_of_column(Column::$core_column, key.clone())?;
+                Ok(
+                    Self::decode(Column::$core_column, key, head, $convert_value)?
+                        .map(std::borrow::Cow::Owned),
+                )
             }
 
             fn contains_key(
@@ -110,7 +271,7 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
                 );
-                let head = self.value_of_column(Column::$core_column, $convert_key(key));
+                let head = self.value_of_column(Column::$core_column, $convert_key(key))?;
                 Ok(head.is_some())
             }
         }
@@ -127,15 +288,12 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key)),
                 );
-                let head = self.value_of_column(Column::$core_column, $convert_key(key));
+                let head = self.value_of_column(Column::$core_column, $convert_key(key))?;
                 let Some(value) = head else {
                     return Ok(false);
                 };
 
-                if offset > value.len() || offset.saturating_add(buf.len()) > value.len() {
-                    return Err(Error::CannotRead);
-                }
-                buf.copy_from_slice(&value[offset..][..buf.len()]);
+                Self::copy_value(Column::$core_column, &value, offset, buf)?;
                 Ok(true)
             }
 
@@ -143,11 +301,12 @@ macro_rules! storage_rw {
                 &self,
                 key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
             ) -> Result<Option<Vec<u8>>, Self::Error> {
-                todo!(
+                tracing::debug!(
                     "{} read_alloc {}",
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
-                )
+                );
+                self.value_of_column(Column::$core_column, $convert_key(key))
             }
         }
 
@@ -165,13 +324,31 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
                 );
-                Ok(self
-                    .replace_column(
-                        Column::$core_column,
-                        $convert_key(key),
-                        Some($convert_value_back(value)),
-                    )
-                    .map($convert_value))
+                let key = $convert_key(key);
+                let previous = self.replace_column(
+                    Column::$core_column,
+                    key.clone(),
+                    Some($convert_value_back(value)),
+                )?;
+                Self::decode(Column::$core_column, key, previous, $convert_value)
+            }
+
+            fn insert(
+                &mut self,
+                key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
+                value: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Value,
+            ) -> Result<(), Self::Error> {
+                tracing::debug!(
+                    "{} insert {} (value={value:?})",
+                    stringify!($core_column),
+                    hex::encode(&$convert_key(key))
+                );
+                self.write_column(
+                    Column::$core_column,
+                    $convert_key(key),
+                    Some($convert_value_back(value)),
+                );
+                Ok(())
             }
 
             fn take(
@@ -186,9 +363,22 @@ macro_rules! storage_rw {
                     stringify!($core_column),
                     hex::encode(&$convert_key(key))
                 );
-                Ok(self
-                    .replace_column(Column::$core_column, $convert_key(key), None)
-                    .map($convert_value))
+                let key = $convert_key(key);
+                let previous = self.replace_column(Column::$core_column, key.clone(), None)?;
+                Self::decode(Column::$core_column, key, previous, $convert_value)
+            }
+
+            fn remove(
+                &mut self,
+                key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
+            ) -> Result<(), Self::Error> {
+                tracing::debug!(
+                    "{} remove {}",
+                    stringify!($core_column),
+                    hex::encode(&$convert_key(key))
+                );
+                self.write_column(Column::$core_column, $convert_key(key), None);
+                Ok(())
             }
         }
 
@@ -196,25 +386,40 @@ macro_rules! storage_rw {
             fn write_bytes(
                 &mut self,
                 key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
-                _buf: &[u8],
+                buf: &[u8],
             ) -> Result<(), Self::Error> {
-                todo!("write_bytes {key:?}")
+                tracing::debug!(
+                    "{} write_bytes {}",
+                    stringify!($core_column),
+                    hex::encode(&$convert_key(key))
+                );
+                self.write_column(Column::$core_column, $convert_key(key), Some(buf.to_vec()));
+                Ok(())
             }
 
             fn replace_bytes(
                 &mut self,
                 key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
-                _buf: &[u8],
+                buf: &[u8],
             ) -> Result<Option<Vec<u8>>, Self::Error> {
-                tracing::debug!("{} replace_bytes {key:?}", stringify!($core_column));
-                Ok(self.replace_column(Column::$core_column, $convert_key(key), None))
+                tracing::debug!(
+                    "{} replace_bytes {}",
+                    stringify!($core_column),
+                    hex::encode(&$convert_key(key))
+                );
+                self.replace_column(Column::$core_column, $convert_key(key), Some(buf.to_vec()))
             }
 
             fn take_bytes(
                 &mut self,
                 key: &<$vm_type as fuel_vm::fuel_storage::Mappable>::Key,
             ) -> Result<Option<Vec<u8>>, Self::Error> {
-                todo!("take_bytes {key:?}")
+                tracing::debug!(
+                    "{} take_bytes {}",
+                    stringify!($core_column),
+                    hex::encode(&$convert_key(key))
+                );
+                self.replace_column(Column::$core_column, $convert_key(key), None)
             }
         }
     };
@@ -223,37 +428,32 @@ macro_rules! storage_rw {
 storage_rw!(
     ContractsRawCode,
     |key: &ContractId| -> Vec<u8> { (**key).to_vec() },
-    |data| todo!("ContractsRawCode from bytes {data:?}"),
-    |data| -> Vec<u8> { todo!("ContractsRawCode to bytes {data:?}") },
+    |data| -> Option<Contract> { Some(Contract::from(data)) },
+    |data: &[u8]| -> Vec<u8> { data.to_vec() },
 );
 storage_rw!(
     ContractsState,
     |key: &ContractsStateKey| -> Vec<u8> { key.as_ref().into() },
-    |data| { ContractsStateData(data) },
+    |data| { Some(ContractsStateData(data)) },
     |data: &[u8]| -> Vec<u8> { data.to_vec() },
 );
 storage_rw!(
     ContractsAssets,
     |key: &ContractsAssetKey| -> Vec<u8> { key.as_ref().into() },
-    |data| {
-        assert_eq!(data.len(), 8);
-        let mut buffer = [0u8; 8];
-        buffer.copy_from_slice(&data);
-        u64::from_be_bytes(buffer)
-    },
+    |data: Vec<u8>| { <[u8; 8]>::try_from(data).ok().map(u64::from_be_bytes) },
     |data: &u64| -> Vec<u8> { data.to_be_bytes().to_vec() },
 );
 storage_rw!(
     UploadedBytecodes,
     |key: &Bytes32| -> Vec<u8> { key.as_ref().into() },
-    |data| todo!("UploadedBytecodes from bytes {data:?}"),
-    |data| -> Vec<u8> { todo!("UploadedBytecodes to bytes {data:?}") },
+    |data| -> Option<UploadedBytecode> { Postcard::decode(&data).ok() },
+    |data: &UploadedBytecode| -> Vec<u8> { Postcard::encode(data).as_bytes().into_owned() },
 );
 storage_rw!(
     BlobData = Blobs,
     |key: &BlobId| -> Vec<u8> { key.as_ref().into() },
-    |data| todo!("BlobData from bytes {data:?}"),
-    |data| -> Vec<u8> { todo!("BlobData to bytes {data:?}") },
+    |data| -> Option<BlobBytes> { Some(BlobBytes::from(data)) },
+    |data: &[u8]| -> Vec<u8> { data.to_vec() },
 );
 
 impl ContractsAssetsStorage for ShallowStorage {}
@@ -264,8 +464,18 @@ pub enum Error {
     InvalidBlock,
     /// The requested key is out of the available keyspace
     KeyspaceOverflow,
-    /// Read offset too large, or buffer too small
+    /// Read offset past the end of the value, or reading past the end of a value that
+    /// isn't zero-filled
     CannotRead,
+    /// fuel-core didn't find the header of a past block
+    MissingBlockHeader(BlockHeight),
+    /// Execution read a key that fuel-core didn't read when executing the block,
+    /// so its value is unknown
+    MissingReplayKey { column: Column, key: Vec<u8> },
+    /// A value in the replay couldn't be decoded as the type stored in its column
+    InvalidValue { column: Column, key: Vec<u8> },
+    /// The replay or a snapshot has an entry in a column that doesn't exist
+    InvalidColumn(u32),
 }
 impl From<Error> for RuntimeError<Error> {
     fn from(e: Error) -> Self {
@@ -300,9 +510,7 @@ impl InterpreterStorage for ShallowStorage {
         match height {
             height if height > self.block_height => Err(Error::InvalidBlock),
             height if height == self.block_height => Ok(self.timestamp.0),
-            height => {
-                todo!("timestamp {height:?}");
-            }
+            height => Ok(self.past_block(height)?.header().time().0),
         }
     }
 
@@ -315,7 +523,8 @@ impl InterpreterStorage for ShallowStorage {
         if block_height >= self.block_height || block_height == Default::default() {
             Ok(Bytes32::zeroed())
         } else {
-            todo!("block_hash {block_height:?}");
+            let block_id = self.past_block(block_height)?.id();
+            Ok(Bytes32::from(<[u8; 32]>::from(block_id)))
         }
     }
 
@@ -325,18 +534,43 @@ impl InterpreterStorage for ShallowStorage {
 
     fn set_consensus_parameters(
         &mut self,
-        _version: u32,
-        _consensus_parameters: &fuel_vm::prelude::ConsensusParameters,
+        version: u32,
+        consensus_parameters: &fuel_vm::prelude::ConsensusParameters,
     ) -> Result<Option<fuel_vm::prelude::ConsensusParameters>, Self::DataError> {
-        unreachable!("Cannot be called by a script");
+        tracing::debug!("set_consensus_parameters {version}");
+        let key = version.to_be_bytes().to_vec();
+        let previous = self.replace_column(
+            Column::ConsensusParametersVersions,
+            key.clone(),
+            Some(
+                Postcard::encode(consensus_parameters)
+                    .as_bytes()
+                    .into_owned(),
+            ),
+        )?;
+        Self::decode(Column::ConsensusParametersVersions, key, previous, |data| {
+            Postcard::decode(&data).ok()
+        })
     }
 
     fn set_state_transition_bytecode(
         &mut self,
-        _version: u32,
-        _hash: &fuel_vm::prelude::Bytes32,
+        version: u32,
+        hash: &fuel_vm::prelude::Bytes32,
     ) -> Result<Option<fuel_vm::prelude::Bytes32>, Self::DataError> {
-        unreachable!("Cannot be called by a script");
+        tracing::debug!("set_state_transition_bytecode {version} {hash:?}");
+        let key = version.to_be_bytes().to_vec();
+        let previous = self.replace_column(
+            Column::StateTransitionBytecodeVersions,
+            key.clone(),
+            Some(hash.to_vec()),
+        )?;
+        Self::decode(
+            Column::StateTransitionBytecodeVersions,
+            key,
+            previous,
+            |data| Bytes32::try_from(data.as_slice()).ok(),
+        )
     }
 
     fn contract_state_range(
@@ -344,8 +578,10 @@ impl InterpreterStorage for ShallowStorage {
         id: &fuel_vm::prelude::ContractId,
         start_key: &fuel_vm::prelude::Bytes32,
         range: usize,
-    ) -> Result<Vec<Option<std::borrow::Cow<fuel_vm::storage::ContractsStateData>>>, Self::DataError>
-    {
+    ) -> Result<
+        Vec<Option<std::borrow::Cow<'_, fuel_vm::storage::ContractsStateData>>>,
+        Self::DataError,
+    > {
         tracing::debug!("contract_state_range {id:?} {start_key:?} {range:?}");
 
         let mut results = Vec::new();
@@ -353,13 +589,11 @@ impl InterpreterStorage for ShallowStorage {
         let mut key_buffer = Bytes32::zeroed();
         for offset in 0..(range as u64) {
             if offset != 0 {
-                key = key
-                    .checked_add(1.into())
-                    .ok_or_else(|| Error::KeyspaceOverflow)?;
+                key = key.checked_add(1.into()).ok_or(Error::KeyspaceOverflow)?;
             }
 
             key.to_big_endian(key_buffer.as_mut());
-            let state_key = ContractsStateKey::new(id, &key_buffer.into());
+            let state_key = ContractsStateKey::new(id, &key_buffer);
             let value = self
                 .storage::<fuel_vm::storage::ContractsState>()
                 .get(&state_key)?;
@@ -386,9 +620,7 @@ impl InterpreterStorage for ShallowStorage {
         let mut found_unset = 0u32;
         for (idx, value) in values.iter().enumerate() {
             if idx != 0 {
-                key = key
-                    .checked_add(1.into())
-                    .ok_or_else(|| Error::KeyspaceOverflow)?;
+                key = key.checked_add(1.into()).ok_or(Error::KeyspaceOverflow)?;
             }
 
             key.to_big_endian(key_buffer.as_mut());
@@ -398,7 +630,7 @@ impl InterpreterStorage for ShallowStorage {
             )?;
 
             if option.is_none() {
-                found_unset += 1;
+                found_unset = found_unset.saturating_add(1);
             }
         }
 
@@ -419,9 +651,7 @@ impl InterpreterStorage for ShallowStorage {
         let mut found_unset = false;
         for idx in 0..range {
             if idx != 0 {
-                key = key
-                    .checked_add(1.into())
-                    .ok_or_else(|| Error::KeyspaceOverflow)?;
+                key = key.checked_add(1.into()).ok_or(Error::KeyspaceOverflow)?;
             }
 
             key.to_big_endian(key_buffer.as_mut());
@@ -437,3 +667,278 @@ impl InterpreterStorage for ShallowStorage {
         Ok(if found_unset { None } else { Some(()) })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuel_vm::{
+        fuel_storage::{Mappable, StorageInspect, StorageMutate},
+        storage::{ContractsStateKey, MemoryStorage},
+    };
+
+    /// Result of a storage operation, `None` if it failed
+    #[derive(Debug, PartialEq, Eq)]
+    enum Outcome {
+        Read(Option<(bool, Vec<u8>)>),
+        ReadAlloc(Option<Option<Vec<u8>>>),
+        Size(Option<Option<usize>>),
+        Contains(Option<bool>),
+        Write(Option<()>),
+        Replace(Option<Option<Vec<u8>>>),
+        Take(Option<Option<Vec<u8>>>),
+        /// Typed values are compared by their debug representation
+        Get(Option<Option<String>>),
+        Insert(Option<()>),
+        ReplaceValue(Option<Option<String>>),
+        TakeValue(Option<Option<String>>),
+        Remove(Option<()>),
+    }
+
+    /// Reads at these offsets and lengths, around the end of a 10 byte value
+    const READS: [(usize, usize); 7] = [(0, 4), (6, 4), (8, 4), (10, 4), (10, 0), (11, 2), (0, 0)];
+
+    fn reads<S, M>(storage: &S, key: &M::Key) -> Vec<Outcome>
+    where
+        M: Mappable,
+        S: StorageRead<M> + StorageSize<M>,
+    {
+        let mut outcomes: Vec<_> = READS
+            .iter()
+            .map(|&(offset, len)| {
+                let mut buf = vec![0xee; len];
+                Outcome::Read(
+                    StorageRead::<M>::read(storage, key, offset, &mut buf)
+                        .ok()
+                        .map(|found| (found, buf)),
+                )
+            })
+            .collect();
+        outcomes.push(Outcome::ReadAlloc(
+            StorageRead::<M>::read_alloc(storage, key).ok(),
+        ));
+        outcomes.push(Outcome::Size(
+            StorageSize::<M>::size_of_value(storage, key).ok(),
+        ));
+        outcomes.push(Outcome::Contains(
+            StorageInspect::<M>::contains_key(storage, key).ok(),
+        ));
+        outcomes
+    }
+
+    /// Runs the same sequence of operations on a key that starts out absent
+    fn operations<S, M>(storage: &mut S, key: &M::Key) -> Vec<Outcome>
+    where
+        M: Mappable,
+        S: StorageRead<M> + StorageSize<M> + StorageWrite<M>,
+    {
+        let mut outcomes = reads::<S, M>(storage, key);
+        let value: Vec<u8> = (1..=10).collect();
+        outcomes.push(Outcome::Write(
+            StorageWrite::<M>::write_bytes(storage, key, &value).ok(),
+        ));
+        outcomes.extend(reads::<S, M>(storage, key));
+        outcomes.push(Outcome::Replace(
+            StorageWrite::<M>::replace_bytes(storage, key, &[0xaa; 32]).ok(),
+        ));
+        outcomes.push(Outcome::Replace(
+            StorageWrite::<M>::replace_bytes(storage, key, &[0xbb; 3]).ok(),
+        ));
+        outcomes.extend(reads::<S, M>(storage, key));
+        outcomes.push(Outcome::Take(
+            StorageWrite::<M>::take_bytes(storage, key).ok(),
+        ));
+        outcomes.push(Outcome::Take(
+            StorageWrite::<M>::take_bytes(storage, key).ok(),
+        ));
+        outcomes.extend(reads::<S, M>(storage, key));
+        outcomes
+    }
+
+    fn get<S, M>(storage: &S, key: &M::Key) -> Vec<Outcome>
+    where
+        M: Mappable,
+        M::OwnedValue: std::fmt::Debug,
+        S: StorageInspect<M>,
+    {
+        vec![
+            Outcome::Get(
+                StorageInspect::<M>::get(storage, key)
+                    .ok()
+                    .map(|value| value.map(|value| format!("{value:?}"))),
+            ),
+            Outcome::Contains(StorageInspect::<M>::contains_key(storage, key).ok()),
+        ]
+    }
+
+    /// Runs the same sequence of typed operations on a key that starts out absent,
+    /// for columns that can't be accessed as bytes
+    fn typed_operations<S, M>(storage: &mut S, key: &M::Key, values: [&M::Value; 2]) -> Vec<Outcome>
+    where
+        M: Mappable,
+        M::OwnedValue: std::fmt::Debug,
+        S: StorageMutate<M>,
+    {
+        let debug = |value: Option<M::OwnedValue>| value.map(|value| format!("{value:?}"));
+        let mut outcomes = get::<S, M>(storage, key);
+        outcomes.push(Outcome::Insert(
+            StorageMutate::<M>::insert(storage, key, values[0]).ok(),
+        ));
+        outcomes.extend(get::<S, M>(storage, key));
+        outcomes.push(Outcome::ReplaceValue(
+            StorageMutate::<M>::replace(storage, key, values[1])
+                .ok()
+                .map(debug),
+        ));
+        outcomes.extend(get::<S, M>(storage, key));
+        outcomes.push(Outcome::TakeValue(
+            StorageMutate::<M>::take(storage, key).ok().map(debug),
+        ));
+        outcomes.push(Outcome::TakeValue(
+            StorageMutate::<M>::take(storage, key).ok().map(debug),
+        ));
+        outcomes.extend(get::<S, M>(storage, key));
+        outcomes.push(Outcome::Insert(
+            StorageMutate::<M>::insert(storage, key, values[0]).ok(),
+        ));
+        outcomes.push(Outcome::Remove(
+            StorageMutate::<M>::remove(storage, key).ok(),
+        ));
+        outcomes.extend(get::<S, M>(storage, key));
+        outcomes
+    }
+
+    /// Shallow storage where fuel-core read `key` of `column` and didn't find it
+    fn shallow_storage(column: Column, key: &[u8]) -> ShallowStorage {
+        let base = ShallowStorage::initial_storage(vec![StorageReadReplayEvent {
+            column: column.as_u32(),
+            key: key.to_vec(),
+            value: None,
+        }])
+        .unwrap();
+        ShallowStorage {
+            block_height: BlockHeight::new(1),
+            timestamp: Tai64::UNIX_EPOCH,
+            consensus_parameters_version: 0,
+            state_transition_version: 0,
+            coinbase: ContractId::zeroed(),
+            base: Rc::new(base),
+            changes: RefCell::default(),
+            journal: None,
+        }
+    }
+
+    fn assert_conforms<M>(column: Column, key: &M::Key, raw_key: &[u8])
+    where
+        M: Mappable,
+        ShallowStorage: StorageRead<M> + StorageSize<M> + StorageWrite<M>,
+        MemoryStorage: StorageRead<M> + StorageSize<M> + StorageWrite<M>,
+    {
+        let mut memory = MemoryStorage::new(BlockHeight::new(1), ContractId::zeroed());
+        let mut shallow = shallow_storage(column, raw_key);
+        assert_eq!(
+            operations::<_, M>(&mut shallow, key),
+            operations::<_, M>(&mut memory, key)
+        );
+    }
+
+    fn assert_typed_conforms<M>(
+        column: Column,
+        key: &M::Key,
+        raw_key: &[u8],
+        values: [&M::Value; 2],
+    ) where
+        M: Mappable,
+        M::OwnedValue: std::fmt::Debug,
+        ShallowStorage: StorageMutate<M>,
+        MemoryStorage: StorageMutate<M>,
+    {
+        let mut memory = MemoryStorage::new(BlockHeight::new(1), ContractId::zeroed());
+        let mut shallow = shallow_storage(column, raw_key);
+        assert_eq!(
+            typed_operations::<_, M>(&mut shallow, key, values),
+            typed_operations::<_, M>(&mut memory, key, values)
+        );
+    }
+
+    #[test]
+    fn contract_code_behaves_like_memory_storage() {
+        let contract_id = ContractId::new([1; 32]);
+        assert_conforms::<ContractsRawCode>(Column::ContractsRawCode, &contract_id, &*contract_id);
+    }
+
+    #[test]
+    fn contract_state_behaves_like_memory_storage() {
+        let key = ContractsStateKey::new(&ContractId::new([1; 32]), &Bytes32::new([2; 32]));
+        assert_conforms::<ContractsState>(Column::ContractsState, &key, key.as_ref());
+    }
+
+    #[test]
+    fn blobs_behave_like_memory_storage() {
+        let blob_id = BlobId::new([1; 32]);
+        assert_conforms::<BlobData>(Column::Blobs, &blob_id, blob_id.as_ref());
+    }
+
+    #[test]
+    fn balances_behave_like_memory_storage() {
+        let key = ContractsAssetKey::new(&ContractId::new([1; 32]), &AssetId::new([2; 32]));
+        assert_typed_conforms::<ContractsAssets>(
+            Column::ContractsAssets,
+            &key,
+            key.as_ref(),
+            [&5, &0],
+        );
+    }
+
+    #[test]
+    fn uploaded_bytecode_behaves_like_memory_storage() {
+        let root = Bytes32::new([1; 32]);
+        let uncompleted = UploadedBytecode::Uncompleted {
+            bytecode: vec![1, 2, 3],
+            uploaded_subsections_number: 1,
+        };
+        let completed = UploadedBytecode::Completed(vec![1, 2, 3, 4, 5, 6]);
+        assert_typed_conforms::<UploadedBytecodes>(
+            Column::UploadedBytecodes,
+            &root,
+            root.as_ref(),
+            [&uncompleted, &completed],
+        );
+    }
+
+    #[test]
+    fn replacing_uncovered_keys_fails() {
+        let contract_id = ContractId::new([1; 32]);
+        let mut storage = shallow_storage(Column::ContractsRawCode, &[2; 32]);
+        let code: &[u8] = &[1, 2, 3];
+
+        let missing = |error| matches!(error, Error::MissingReplayKey { column: Column::ContractsRawCode, ref key } if *key == *contract_id);
+        assert!(missing(
+            StorageWrite::<ContractsRawCode>::replace_bytes(&mut storage, &contract_id, code)
+                .unwrap_err()
+        ));
+        assert!(missing(
+            StorageWrite::<ContractsRawCode>::take_bytes(&mut storage, &contract_id).unwrap_err()
+        ));
+        assert!(missing(
+            StorageMutate::<ContractsRawCode>::replace(&mut storage, &contract_id, code)
+                .unwrap_err()
+        ));
+        assert!(missing(
+            StorageMutate::<ContractsRawCode>::take(&mut storage, &contract_id).unwrap_err()
+        ));
+
+        // Writes that discard the previous value don't need it, and make it known
+        StorageMutate::<ContractsRawCode>::insert(&mut storage, &contract_id, code).unwrap();
+        assert_eq!(
+            StorageWrite::<ContractsRawCode>::take_bytes(&mut storage, &contract_id).unwrap(),
+            Some(code.to_vec())
+        );
+        let contract_id = ContractId::new([3; 32]);
+        StorageWrite::<ContractsRawCode>::write_bytes(&mut storage, &contract_id, code).unwrap();
+        assert_eq!(
+            StorageWrite::<ContractsRawCode>::replace_bytes(&mut storage, &contract_id, &[])
+                .unwrap(),
+            Some(code.to_vec())
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::{cell::RefCell, collections::HashMap, io, path::Path, rc::Rc};
+
+use fuel_core_types::{services::executor::StorageReadReplayEvent, tai64::Tai64};
+use fuel_vm::{
+    fuel_types::BlockHeight,
+    prelude::{ConsensusParameters, ContractId, Word},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data_source::{read_versioned, write_versioned},
+    shallow_storage::ShallowStorage,
+    StorageError,
+};
+
+/// Version of the snapshot file format, bumped on incompatible changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The block state at a transaction boundary, see [`BlockTracer::on_snapshot`](crate::BlockTracer::on_snapshot).
+/// Pass it to [`trace_snapshot_transaction`](crate::trace_snapshot_transaction) to execute
+/// a transaction on top of that exact state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageSnapshot {
+    pub block_height: BlockHeight,
+    pub timestamp: Tai64,
+    pub consensus_parameters_version: u32,
+    pub state_transition_version: u32,
+    pub coinbase: ContractId,
+    /// Index within the block of the transaction the snapshot was taken before
+    pub tx_index: usize,
+    pub gas_price: Word,
+    pub consensus_params: ConsensusParameters,
+    /// Every known key, in the format of fuel-core's storage replay, ordered by column and key.
+    /// `None` values are keys known to be absent.
+    pub entries: Vec<StorageReadReplayEvent>,
+}
+
+impl StorageSnapshot {
+    /// Copies the state of the block before the transaction at `tx_index`
+    pub(crate) fn new(
+        storage: &ShallowStorage,
+        tx_index: usize,
+        gas_price: Word,
+        consensus_params: &ConsensusParameters,
+    ) -> Self {
+        let mut values: HashMap<_, _> = storage.base.as_ref().clone();
+        for (column, changes) in storage.changes.borrow().iter() {
+            values.entry(*column).or_default().extend(changes.clone());
+        }
+
+        let mut entries: Vec<_> = values
+            .into_iter()
+            .flat_map(|(column, values)| {
+                values
+                    .into_iter()
+                    .map(move |(key, value)| StorageReadReplayEvent {
+                        column: column.as_u32(),
+                        key,
+                        value,
+                    })
+            })
+            .collect();
+        entries.sort_by(|a, b| (a.column, &a.key).cmp(&(b.column, &b.key)));
+
+        Self {
+            block_height: storage.block_height,
+            timestamp: storage.timestamp,
+            consensus_parameters_version: storage.consensus_parameters_version,
+            state_transition_version: storage.state_transition_version,
+            coinbase: storage.coinbase,
+            tx_index,
+            gas_price,
+            consensus_params: consensus_params.clone(),
+            entries,
+        }
+    }
+
+    /// Reads a snapshot file written by [`StorageSnapshot::save`].
+    /// Entries are only checked when the snapshot is traced on.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        read_versioned(path, "snapshot", SNAPSHOT_VERSION)
+    }
+
+    /// Writes the snapshot to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_versioned(path, SNAPSHOT_VERSION, self)
+    }
+}
+
+impl TryFrom<StorageSnapshot> for ShallowStorage {
+    type Error = StorageError;
+
+    fn try_from(snapshot: StorageSnapshot) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_height: snapshot.block_height,
+            timestamp: snapshot.timestamp,
+            consensus_parameters_version: snapshot.consensus_parameters_version,
+            state_transition_version: snapshot.state_transition_version,
+            coinbase: snapshot.coinbase,
+            base: Rc::new(ShallowStorage::initial_storage(snapshot.entries)?),
+            changes: RefCell::default(),
+            journal: None,
+        })
+    }
+}
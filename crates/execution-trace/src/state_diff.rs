@@ -0,0 +1,122 @@
+use fuel_core_storage::column::Column;
+use fuel_vm::prelude::{AssetId, BlobId, Bytes32, ContractId, Word};
+use serde::Serialize;
+
+use crate::shallow_storage::{Error, InnerStorage, ShallowStorage};
+
+/// State changes applied by a transaction, ordered by key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StateDiff {
+    pub storage_slots: Vec<SlotChange>,
+    pub balances: Vec<BalanceChange>,
+    /// Contracts deployed by the transaction
+    pub created_contracts: Vec<ContractId>,
+    /// Blobs uploaded by the transaction
+    pub created_blobs: Vec<BlobId>,
+}
+
+/// A contract storage slot that changed, `None` values mean the slot is unset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotChange {
+    pub contract_id: ContractId,
+    pub key: Bytes32,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+/// A contract balance that changed, `None` values mean the contract never held the asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceChange {
+    pub contract_id: ContractId,
+    pub asset_id: AssetId,
+    pub before: Option<Word>,
+    pub after: Option<Word>,
+}
+
+impl StateDiff {
+    /// Compares the writes of a transaction with the state they are about to be applied to.
+    /// Fails if a changed key or value can't be decoded as the type stored in its column.
+    pub(crate) fn new(state: &ShallowStorage, changes: &InnerStorage) -> Result<Self, Error> {
+        let mut diff = Self::default();
+        for (column, values) in changes {
+            for (key, after) in values {
+                let before = state.lookup(*column, key);
+                if before == *after {
+                    continue;
+                }
+                let invalid = || Error::InvalidValue {
+                    column: *column,
+                    key: key.clone(),
+                };
+                match column {
+                    Column::ContractsState => {
+                        let (contract_id, key) = split_key(key).ok_or_else(invalid)?;
+                        diff.storage_slots.push(SlotChange {
+                            contract_id: ContractId::from(contract_id),
+                            key: Bytes32::from(key),
+                            before,
+                            after: after.clone(),
+                        });
+                    }
+                    Column::ContractsAssets => {
+                        let (contract_id, asset_id) = split_key(key).ok_or_else(invalid)?;
+                        let before = before
+                            .as_deref()
+                            .map(|data| balance(data).ok_or_else(invalid))
+                            .transpose()?;
+                        let after = after
+                            .as_deref()
+                            .map(|data| balance(data).ok_or_else(invalid))
+                            .transpose()?;
+                        // The VM writes zero balances when it reads missing ones, e.g. on CALL
+                        if before.unwrap_or(0) == after.unwrap_or(0) {
+                            continue;
+                        }
+                        diff.balances.push(BalanceChange {
+                            contract_id: ContractId::from(contract_id),
+                            asset_id: AssetId::from(asset_id),
+                            before,
+                            after,
+                        });
+                    }
+                    Column::ContractsRawCode if before.is_none() => {
+                        let id = <[u8; 32]>::try_from(key.as_slice()).map_err(|_| invalid())?;
+                        diff.created_contracts.push(ContractId::from(id));
+                    }
+                    Column::Blobs if before.is_none() => {
+                        let id = <[u8; 32]>::try_from(key.as_slice()).map_err(|_| invalid())?;
+                        diff.created_blobs.push(BlobId::from(id));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        diff.storage_slots
+            .sort_by_key(|change| (change.contract_id, change.key));
+        diff.balances
+            .sort_by_key(|change| (change.contract_id, change.asset_id));
+        diff.created_contracts.sort();
+        diff.created_blobs.sort();
+        Ok(diff)
+    }
+
+    /// Returns true if the transaction didn't change any tracked state
+    pub fn is_empty(&self) -> bool {
+        self.storage_slots.is_empty()
+            && self.balances.is_empty()
+            && self.created_contracts.is_empty()
+            && self.created_blobs.is_empty()
+    }
+}
+
+/// Splits a key made of a contract id and another 32 byte id
+fn split_key(key: &[u8]) -> Option<([u8; 32], [u8; 32])> {
+    let (contract_id, rest) = key.split_first_chunk::<32>()?;
+    Some((*contract_id, <[u8; 32]>::try_from(rest).ok()?))
+}
+
+/// Decodes a balance, stored as 8 big-endian bytes
+fn balance(data: &[u8]) -> Option<Word> {
+    data.try_into().ok().map(Word::from_be_bytes)
+}
@@ -0,0 +1,66 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use fuel_core_storage::column::Column;
+use fuel_vm::prelude::ContractId;
+
+/// Accesses recorded by the storage, shared between the block state and the interpreters using it
+pub(crate) type StorageJournal = Rc<Journal>;
+
+/// Storage accesses not yet passed to the tracer. Accesses are only recorded while
+/// the transaction is single-stepped, as nothing would pass them to the tracer otherwise.
+#[derive(Debug, Default)]
+pub(crate) struct Journal {
+    recording: Cell<bool>,
+    accesses: RefCell<Vec<StorageAccess>>,
+}
+
+impl Journal {
+    pub fn is_recording(&self) -> bool {
+        self.recording.get()
+    }
+
+    pub fn set_recording(&self, recording: bool) {
+        self.recording.set(recording);
+    }
+
+    /// Records the access, unless recording is off
+    pub fn push(&self, access: StorageAccess) {
+        if self.is_recording() {
+            self.accesses.borrow_mut().push(access);
+        }
+    }
+
+    /// Takes the accesses recorded so far
+    pub fn take(&self) -> Vec<StorageAccess> {
+        self.accesses.take()
+    }
+}
+
+/// Whether a storage access read or modified the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageAccessKind {
+    Read,
+    Write,
+}
+
+/// A single read or write of the block state, see [`TraceConfig::storage_journal`](crate::TraceConfig::storage_journal)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageAccess {
+    /// Column in fuel-core's database that was accessed
+    pub column: Column,
+    /// Key in the column, encoded like fuel-core does
+    pub key: Vec<u8>,
+    pub kind: StorageAccessKind,
+    /// Value before the access, `None` if there was none
+    pub old_value: Option<Vec<u8>>,
+    /// Value after the access, `None` if it was removed. Same as `old_value` for reads.
+    pub new_value: Option<Vec<u8>>,
+    /// Index of the instruction within the transaction that did the access,
+    /// `None` if it happened while the interpreter was set up, before the first instruction
+    pub instruction_index: Option<u64>,
+    /// Contract whose code did the access, `None` for the script or a predicate
+    pub contract_id: Option<ContractId>,
+}
@@ -0,0 +1,112 @@
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_vm::{
+    fuel_types::BlockHeight,
+    prelude::{Receipt, RegId},
+};
+
+use crate::{StateDiff, StorageAccess, StorageSnapshot, TraceContext, TraceDiagnostic, VmView};
+
+/// What to do after a tracer hook returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceFlow {
+    /// Keep single-stepping
+    #[default]
+    Continue,
+    /// Execute the rest of the current transaction, including its script
+    /// if this is returned from a predicate, without passing it to the tracer
+    SkipTransaction,
+    /// Stop tracing the block right away. The current transaction is left unfinished,
+    /// so `on_tx_end` isn't called for it, but `on_block_end` still is.
+    StopBlock,
+}
+
+impl From<()> for TraceFlow {
+    fn from(_: ()) -> Self {
+        Self::Continue
+    }
+}
+
+/// Hooks called while tracing a block.
+/// Only `on_instruction` is required, the rest default to doing nothing.
+///
+/// Any `FnMut(&dyn VmView, &TraceContext)` closure returning either `()` or a [`TraceFlow`]
+/// is a tracer that only handles instructions.
+pub trait BlockTracer {
+    /// Called before any transaction of the block is executed
+    fn on_block_start(&mut self, _block_height: BlockHeight) {}
+
+    /// Called with the block state before each transaction, traced or not. Only called if
+    /// [`TraceConfig::snapshots`](crate::TraceConfig::snapshots) is enabled.
+    fn on_snapshot(&mut self, _ctx: &TraceContext, _snapshot: &StorageSnapshot) {}
+
+    /// Called before a traced transaction is executed, including its predicates
+    fn on_tx_start(&mut self, _ctx: &TraceContext) {}
+
+    /// Called before each executed instruction, and once after the last one
+    fn on_instruction(&mut self, vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow;
+
+    /// Called for each new receipt, before `on_instruction` is called for the next step.
+    /// `index` is the position of the receipt within the transaction receipts.
+    fn on_receipt(
+        &mut self,
+        _vm: &dyn VmView,
+        _ctx: &TraceContext,
+        _index: usize,
+        _receipt: &Receipt,
+    ) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    /// Called for each read or write of the block state, before the receipts and
+    /// `on_instruction` of the next step. Only called if
+    /// [`TraceConfig::storage_journal`](crate::TraceConfig::storage_journal) is enabled.
+    fn on_storage_access(
+        &mut self,
+        _vm: &dyn VmView,
+        _ctx: &TraceContext,
+        _access: &StorageAccess,
+    ) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    /// Called before an `ECAL` instruction is executed, and before `on_instruction` for it.
+    /// `registers` are the four register operands of the instruction.
+    fn on_ecal(
+        &mut self,
+        _vm: &dyn VmView,
+        _ctx: &TraceContext,
+        _registers: [RegId; 4],
+    ) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    /// Called when the replay of a transaction diverges from the chain but tracing continues,
+    /// see [`ReplayPolicy`](crate::ReplayPolicy). Also called for transactions that aren't traced.
+    fn on_diagnostic(&mut self, _ctx: &TraceContext, _diagnostic: &TraceDiagnostic) {}
+
+    /// Called with the state changes a traced transaction applied, right before `on_tx_end`.
+    /// Empty for reverted transactions and ones whose changes were discarded.
+    fn on_state_diff(&mut self, _ctx: &TraceContext, _diff: &StateDiff) {}
+
+    /// Called after a traced transaction has been executed and its receipts verified
+    fn on_tx_end(
+        &mut self,
+        _ctx: &TraceContext,
+        _receipts: &[Receipt],
+        _status: &TransactionStatus,
+    ) {
+    }
+
+    /// Called after all transactions of the block have been executed, or tracing was stopped
+    fn on_block_end(&mut self, _block_height: BlockHeight) {}
+}
+
+impl<F, R> BlockTracer for F
+where
+    F: FnMut(&dyn VmView, &TraceContext) -> R,
+    R: Into<TraceFlow>,
+{
+    fn on_instruction(&mut self, vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        self(vm, ctx).into()
+    }
+}
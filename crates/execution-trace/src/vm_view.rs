@@ -0,0 +1,76 @@
+use fuel_vm::{
+    consts::WORD_SIZE,
+    prelude::{
+        CallFrame, ContractId, GasCosts, Instruction, Interpreter, MemoryInstance, Receipt, RegId,
+        Word,
+    },
+};
+
+/// Read-only access to the state of a traced interpreter.
+/// This is independent of the transaction type, so that predicates
+/// of all transaction kinds can be traced using the same callback.
+pub trait VmView {
+    /// Current values of the registers
+    fn registers(&self) -> &[Word];
+    /// The VM memory
+    fn memory(&self) -> &MemoryInstance;
+    /// Receipts produced so far
+    fn receipts(&self) -> &[Receipt];
+    /// Gas costs of the consensus parameters in use
+    fn gas_costs(&self) -> &GasCosts;
+
+    /// The instruction at `$pc`, i.e. the next one to be executed.
+    /// `None` if it can't be read or isn't a valid instruction.
+    fn instruction(&self) -> Option<Instruction> {
+        let bytes: [u8; 4] = self.memory().read_bytes(self.registers()[RegId::PC]).ok()?;
+        Instruction::try_from(bytes).ok()
+    }
+
+    /// Contract whose code is being executed, read from the current call frame.
+    /// `None` when executing the script or a predicate.
+    fn contract_id(&self) -> Option<ContractId> {
+        match self.registers()[RegId::FP] {
+            0 => None,
+            // The called contract id is the first field of a call frame
+            fp => self.memory().read_bytes(fp).ok().map(ContractId::new),
+        }
+    }
+
+    /// Number of call frames on the stack, found by following the saved `$fp` of each frame
+    fn call_depth(&self) -> usize {
+        let saved_fp_offset = CallFrame::registers_offset()
+            .saturating_add(WORD_SIZE.saturating_mul(usize::from(RegId::FP.to_u8())));
+
+        let mut depth = 0usize;
+        let mut fp = self.registers()[RegId::FP];
+        while fp != 0 {
+            depth = depth.saturating_add(1);
+            match self
+                .memory()
+                .read_bytes(fp.saturating_add(saved_fp_offset as Word))
+            {
+                Ok(saved_fp) => fp = Word::from_be_bytes(saved_fp),
+                Err(_) => break,
+            }
+        }
+        depth
+    }
+}
+
+impl<S, Tx, Ecal> VmView for Interpreter<MemoryInstance, S, Tx, Ecal> {
+    fn registers(&self) -> &[Word] {
+        Interpreter::registers(self)
+    }
+
+    fn memory(&self) -> &MemoryInstance {
+        Interpreter::memory(self)
+    }
+
+    fn receipts(&self) -> &[Receipt] {
+        Interpreter::receipts(self)
+    }
+
+    fn gas_costs(&self) -> &GasCosts {
+        Interpreter::gas_costs(self)
+    }
+}
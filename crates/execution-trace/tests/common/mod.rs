@@ -0,0 +1,553 @@
+//! Builds in-memory blocks for tracing, using fuel-vm's `MemoryStorage` to produce
+//! the receipts, gas and fee that fuel-core would have reported for them.
+
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use fuel_core_client::client::types::{TransactionResponse, TransactionStatus, TransactionType};
+use fuel_core_storage::{
+    codec::{postcard::Postcard, Encode, Encoder},
+    column::Column,
+};
+use fuel_core_types::{
+    blockchain::{
+        block::Block,
+        header::{ConsensusHeader, PartialBlockHeader},
+    },
+    services::executor::StorageReadReplayEvent,
+    tai64::Tai64,
+};
+use fuel_execution_trace::{
+    BlockInfo, BlockTracer, MemoryDataSource, StateDiff, TraceContext, TraceFlow, VmView,
+};
+use fuel_vm::{
+    checked_transaction::{IntoChecked, Ready},
+    fuel_asm::op,
+    fuel_tx::{
+        field::{Inputs, MaxFeeLimit},
+        input, output, Buildable, TransactionBuilder, TxPointer, UtxoId,
+    },
+    fuel_types::BlockHeight,
+    interpreter::{EcalHandler, InterpreterParams, NotSupportedEcal},
+    prelude::*,
+    storage::{BlobData, ContractsAssetsStorage, ContractsRawCode, MemoryStorage},
+};
+
+pub const SCRIPT_GAS_LIMIT: Word = 1_000_000;
+/// Gas limit of the predicates added by [`TestBlock::predicate_script`]
+pub const PREDICATE_GAS_USED: Word = 100_000;
+
+/// A block under construction. Transactions are executed as they are added.
+pub struct TestBlock {
+    pub height: BlockHeight,
+    pub time: Tai64,
+    pub params: ConsensusParameters,
+    /// State of the chain as fuel-core would see it
+    storage: MemoryStorage,
+    reads: Vec<StorageReadReplayEvent>,
+    txs: Vec<(TxId, TransactionResponse)>,
+}
+
+impl TestBlock {
+    pub fn new(height: u32) -> Self {
+        let height = BlockHeight::new(height);
+        Self {
+            height,
+            time: Tai64::from_unix(1_700_000_000),
+            params: ConsensusParameters::standard(),
+            storage: MemoryStorage::new(height, ContractId::zeroed()),
+            reads: Vec::new(),
+            txs: Vec::new(),
+        }
+    }
+
+    /// Adds a read to the storage replay, without changing the state
+    pub fn read(&mut self, column: Column, key: impl Into<Vec<u8>>, value: Option<Vec<u8>>) {
+        self.reads.push(StorageReadReplayEvent {
+            column: column.as_u32(),
+            key: key.into(),
+            value,
+        });
+    }
+
+    /// Makes a contract exist before the block, stored as raw bytes like fuel-core does
+    pub fn existing_contract(&mut self, code: &[Instruction]) -> ContractId {
+        let code: Vec<u8> = code.iter().copied().collect();
+        let contract_id = ContractId::new([0xc0; 32]);
+        self.storage
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&contract_id, &code)
+            .unwrap();
+        self.read(Column::ContractsRawCode, *contract_id, Some(code));
+        contract_id
+    }
+
+    /// Makes a blob exist before the block, stored as raw bytes like fuel-core does
+    pub fn existing_blob(&mut self, data: &[u8]) -> BlobId {
+        let blob_id = BlobId::compute(data);
+        self.storage
+            .storage_as_mut::<BlobData>()
+            .insert(&blob_id, data)
+            .unwrap();
+        self.read(Column::Blobs, *blob_id, Some(data.to_vec()));
+        blob_id
+    }
+
+    /// Gives a contract a balance before the block, stored as 8 big-endian bytes like fuel-core does
+    pub fn existing_balance(&mut self, contract_id: ContractId, asset_id: AssetId, amount: Word) {
+        self.storage
+            .contract_asset_id_balance_insert(&contract_id, &asset_id, amount)
+            .unwrap();
+        let key: Vec<u8> = contract_id.iter().chain(asset_id.iter()).copied().collect();
+        self.read(
+            Column::ContractsAssets,
+            key,
+            Some(amount.to_be_bytes().to_vec()),
+        );
+    }
+
+    /// Makes fuel-core read the header of a past block, as it does for `TIME` and `BHSH`.
+    /// Returns the id of that block.
+    pub fn past_block(&mut self, height: u32, time: Tai64) -> Bytes32 {
+        let header = PartialBlockHeader {
+            application: Default::default(),
+            consensus: ConsensusHeader {
+                prev_root: Bytes32::zeroed(),
+                height: height.into(),
+                time,
+                generated: Default::default(),
+            },
+        };
+        let block = Block::new(header, Vec::new(), &[], Bytes32::zeroed())
+            .unwrap()
+            .compress(&self.params.chain_id());
+        let value = Postcard::encode(&block).as_bytes().into_owned();
+        self.read(
+            Column::FuelBlocks,
+            BlockHeight::new(height).to_bytes(),
+            Some(value),
+        );
+        Bytes32::from(<[u8; 32]>::from(block.id()))
+    }
+
+    /// Adds a transaction deploying a contract with the given code
+    pub fn deploy(&mut self, code: &[Instruction]) -> (TxId, ContractId) {
+        let code: Vec<u8> = code.iter().copied().collect();
+        let salt = Salt::zeroed();
+        let contract = Contract::from(code.clone());
+        let contract_id = contract.id(&salt, &contract.root(), &Contract::default_state_root());
+        // fuel-core checks that the contract doesn't exist yet
+        self.read(Column::ContractsRawCode, *contract_id, None);
+
+        let tx = self
+            .fee_input(TransactionBuilder::create(code.into(), salt, Vec::new()))
+            .add_contract_created()
+            .finalize();
+        let tx_id = self.execute(tx, |vm, ready| vm.deploy(ready));
+        (tx_id, contract_id)
+    }
+
+    /// Adds a transaction uploading `bytecode` in a single subsection.
+    /// Returns the transaction and the root of the bytecode.
+    pub fn upload(&mut self, bytecode: &[u8]) -> (TxId, Bytes32) {
+        let subsection = UploadSubsection::split_bytecode(bytecode, bytecode.len())
+            .unwrap()
+            .remove(0);
+        let root = subsection.root;
+        // fuel-core checks what was uploaded under the root before
+        self.read(Column::UploadedBytecodes, *root, None);
+
+        let body = UploadBody {
+            root,
+            witness_index: 1,
+            subsection_index: subsection.subsection_index,
+            subsections_number: subsection.subsections_number,
+            proof_set: subsection.proof_set,
+        };
+        let tx = self
+            .fee_input(TransactionBuilder::upload(body))
+            .add_witness(subsection.subsection.into())
+            .finalize();
+        let tx_id = self.execute(tx, |vm, ready| vm.upload(ready));
+        (tx_id, root)
+    }
+
+    /// Adds a transaction making the uploaded bytecode with the given root the next state
+    /// transition function
+    pub fn upgrade(&mut self, root: Bytes32) -> TxId {
+        // fuel-core checks that the next version isn't taken
+        self.read(
+            Column::StateTransitionBytecodeVersions,
+            1u32.to_be_bytes(),
+            None,
+        );
+        let tx = self
+            .fee_input(TransactionBuilder::upgrade(
+                UpgradePurpose::StateTransition { root },
+            ))
+            .finalize();
+        self.execute(tx, |vm, ready| vm.upgrade(ready))
+    }
+
+    /// Adds a transaction creating a blob with the given data
+    pub fn blob(&mut self, data: &[u8]) -> (TxId, BlobId) {
+        let blob_id = BlobId::compute(data);
+        // fuel-core checks that the blob doesn't exist yet
+        self.read(Column::Blobs, *blob_id, None);
+
+        let tx = self
+            .fee_input(TransactionBuilder::blob(BlobBody {
+                id: blob_id,
+                witness_index: 1,
+            }))
+            .add_witness(data.to_vec().into())
+            .finalize();
+        let tx_id = self.execute(tx, |vm, ready| vm.blob(ready));
+        (tx_id, blob_id)
+    }
+
+    /// Adds a script transaction that has the given contracts as inputs
+    pub fn script(
+        &mut self,
+        script: &[Instruction],
+        data: Vec<u8>,
+        contracts: &[ContractId],
+    ) -> TxId {
+        self.script_with_inputs(script, data, contracts, Vec::new(), NotSupportedEcal)
+    }
+
+    /// Adds a script transaction executed with the given `ECAL` handler
+    pub fn ecal_script<Ecal: EcalHandler>(
+        &mut self,
+        script: &[Instruction],
+        data: Vec<u8>,
+        ecal: Ecal,
+    ) -> TxId {
+        self.script_with_inputs(script, data, &[], Vec::new(), ecal)
+    }
+
+    /// Adds a script transaction spending a coin owned by `predicate`, which is its first input.
+    /// fuel-core verifies predicates before the block, so they don't affect the status.
+    pub fn predicate_script(
+        &mut self,
+        script: &[Instruction],
+        predicate: &[Instruction],
+    ) -> (TxId, Address) {
+        let predicate: Vec<u8> = predicate.iter().copied().collect();
+        let owner = Input::predicate_owner(&predicate);
+        let coin = Input::coin_predicate(
+            UtxoId::new(Bytes32::new([u8::try_from(self.txs.len()).unwrap(); 32]), 1),
+            owner,
+            1_000,
+            *self.params.base_asset_id(),
+            TxPointer::default(),
+            PREDICATE_GAS_USED,
+            predicate,
+            Vec::new(),
+        );
+        let tx_id = self.script_with_inputs(script, Vec::new(), &[], vec![coin], NotSupportedEcal);
+        (tx_id, owner)
+    }
+
+    /// Adds a script transaction with the given contracts as inputs, followed by `inputs`
+    fn script_with_inputs<Ecal: EcalHandler>(
+        &mut self,
+        script: &[Instruction],
+        data: Vec<u8>,
+        contracts: &[ContractId],
+        inputs: Vec<Input>,
+        ecal: Ecal,
+    ) -> TxId {
+        let mut builder = TransactionBuilder::script(script.iter().copied().collect(), data);
+        builder.script_gas_limit(SCRIPT_GAS_LIMIT);
+        for (index, contract_id) in contracts.iter().enumerate() {
+            builder
+                .add_input(Input::contract(
+                    UtxoId::default(),
+                    Bytes32::zeroed(),
+                    Bytes32::zeroed(),
+                    TxPointer::default(),
+                    *contract_id,
+                ))
+                .add_output(Output::contract(
+                    u16::try_from(index).unwrap(),
+                    Bytes32::zeroed(),
+                    Bytes32::zeroed(),
+                ));
+        }
+        for input in inputs {
+            builder.add_input(input);
+        }
+        let tx = self.fee_input(builder).finalize();
+        let inputs = tx.inputs().clone();
+        let tx_id = self.tx_id(&tx);
+        let ready = self.ready(tx);
+
+        let mut vm = self.vm(ecal);
+        let result = vm.transact(ready).unwrap();
+        let reverted = matches!(result.state(), ProgramState::Revert(_));
+        let receipts = result.receipts().to_vec();
+        let mut tx = result.tx().clone();
+        // The interpreter zeroes the predicate gas of its copy, fuel-core keeps the inputs as sent
+        *tx.inputs_mut() = inputs;
+        // Like fuel-core, the state changes of reverted scripts are discarded
+        if !reverted {
+            self.storage = vm.as_ref().clone();
+        }
+        self.push(tx_id, tx, receipts);
+        tx_id
+    }
+
+    /// Adds a script transaction calling the contract with [`call_script`]
+    pub fn call(&mut self, contract_id: ContractId) -> TxId {
+        // fuel-core reads the balance of the forwarded asset, even if nothing is forwarded
+        let balance_key: Vec<u8> = contract_id
+            .iter()
+            .chain(AssetId::zeroed().iter())
+            .copied()
+            .collect();
+        self.read(Column::ContractsAssets, balance_key, None);
+        self.script(&call_script(), call_data(contract_id), &[contract_id])
+    }
+
+    /// Adds the mint transaction ending the block and its data to `source`
+    pub fn finish(mut self, source: &mut MemoryDataSource) {
+        let mint = Transaction::mint(
+            TxPointer::new(self.height, u16::try_from(self.txs.len()).unwrap()),
+            input::contract::Contract {
+                utxo_id: UtxoId::default(),
+                balance_root: Bytes32::zeroed(),
+                state_root: Bytes32::zeroed(),
+                tx_pointer: TxPointer::default(),
+                contract_id: ContractId::zeroed(),
+            },
+            output::contract::Contract {
+                input_index: 0,
+                balance_root: Bytes32::zeroed(),
+                state_root: Bytes32::zeroed(),
+            },
+            0,
+            *self.params.base_asset_id(),
+            0,
+        );
+        let mint_id = self.tx_id(&mint);
+        self.txs.push((
+            mint_id,
+            TransactionResponse {
+                transaction: TransactionType::Known(mint.into()),
+                status: self.status(Vec::new(), 0, 0),
+            },
+        ));
+
+        source.blocks.insert(
+            self.height,
+            BlockInfo {
+                height: self.height,
+                time: self.time,
+                consensus_parameters_version: 0,
+                state_transition_bytecode_version: 0,
+                transactions: self.txs.iter().map(|(tx_id, _)| *tx_id).collect(),
+            },
+        );
+        source.transactions.extend(self.txs);
+        source.consensus_parameters.insert(0, self.params);
+        source.storage_reads.insert(self.height, self.reads);
+    }
+
+    fn fee_input<Tx: Buildable>(
+        &self,
+        mut builder: TransactionBuilder<Tx>,
+    ) -> TransactionBuilder<Tx> {
+        builder
+            .with_params(self.params.clone())
+            .add_input(Input::coin_signed(
+                UtxoId::new(Bytes32::new([u8::try_from(self.txs.len()).unwrap(); 32]), 0),
+                Address::zeroed(),
+                1_000_000,
+                *self.params.base_asset_id(),
+                TxPointer::default(),
+                0,
+            ))
+            .add_witness(Witness::default());
+        builder
+    }
+
+    /// Executes a transaction without a script and adds it
+    fn execute<Tx, E>(
+        &mut self,
+        tx: Tx,
+        execute: impl FnOnce(
+            &mut Interpreter<MemoryInstance, MemoryStorage, Script>,
+            Ready<Tx>,
+        ) -> Result<Tx, E>,
+    ) -> TxId
+    where
+        Tx: IntoChecked + Chargeable + UniqueIdentifier + Into<Transaction>,
+        E: std::fmt::Debug,
+    {
+        let tx_id = self.tx_id(&tx);
+        let ready = self.ready(tx);
+        let mut vm = self.vm(NotSupportedEcal);
+        let tx = execute(&mut vm, ready).unwrap();
+        self.storage = vm.as_ref().clone();
+        self.push(tx_id, tx, Vec::new());
+        tx_id
+    }
+
+    fn tx_id<Tx: UniqueIdentifier>(&self, tx: &Tx) -> TxId {
+        tx.id(&self.params.chain_id())
+    }
+
+    fn ready<Tx: IntoChecked + Chargeable>(&self, tx: Tx) -> Ready<Tx> {
+        tx.into_checked_basic(self.height, &self.params)
+            .unwrap()
+            .into_ready(
+                0,
+                self.params.gas_costs(),
+                self.params.fee_params(),
+                Some(self.height),
+            )
+            .unwrap()
+    }
+
+    fn vm<Ecal: EcalHandler>(
+        &self,
+        ecal: Ecal,
+    ) -> Interpreter<MemoryInstance, MemoryStorage, Script, Ecal> {
+        Interpreter::with_storage_and_ecal(
+            MemoryInstance::new(),
+            self.storage.clone(),
+            InterpreterParams::new(0, &self.params),
+            ecal,
+        )
+    }
+
+    /// Records a transaction with the gas and fee fuel-core computes for it
+    fn push<Tx>(&mut self, tx_id: TxId, tx: Tx, receipts: Vec<Receipt>)
+    where
+        Tx: Chargeable + Into<Transaction>,
+    {
+        let used_gas = receipts
+            .iter()
+            .find_map(|receipt| match receipt {
+                Receipt::ScriptResult { gas_used, .. } => Some(*gas_used),
+                _ => None,
+            })
+            .unwrap_or(0);
+        let (gas_costs, fee_params) = (self.params.gas_costs(), self.params.fee_params());
+        let min_gas = tx.min_gas(gas_costs, fee_params);
+        let refund = tx.refund_fee(gas_costs, fee_params, used_gas, 0).unwrap();
+        let status = self.status(receipts, min_gas + used_gas, tx.max_fee_limit() - refund);
+        self.txs.push((
+            tx_id,
+            TransactionResponse {
+                transaction: TransactionType::Known(tx.into()),
+                status,
+            },
+        ));
+    }
+
+    fn status(
+        &self,
+        receipts: Vec<Receipt>,
+        total_gas: Word,
+        total_fee: Word,
+    ) -> TransactionStatus {
+        let failed = receipts.iter().any(|receipt| {
+            matches!(
+                receipt,
+                Receipt::ScriptResult { result, .. } if *result != ScriptExecutionResult::Success
+            )
+        });
+        if failed {
+            TransactionStatus::Failure {
+                block_height: self.height,
+                time: self.time,
+                total_gas,
+                total_fee,
+                reason: "Reverted".to_string(),
+                program_state: None,
+                receipts,
+            }
+        } else {
+            TransactionStatus::Success {
+                block_height: self.height,
+                time: self.time,
+                total_gas,
+                total_fee,
+                program_state: None,
+                receipts,
+            }
+        }
+    }
+}
+
+/// Keeps the receipts of every traced transaction
+#[derive(Default)]
+pub struct ReceiptCollector {
+    pub receipts: Vec<(TxId, Vec<Receipt>)>,
+}
+
+impl BlockTracer for ReceiptCollector {
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    fn on_tx_end(&mut self, ctx: &TraceContext, receipts: &[Receipt], _: &TransactionStatus) {
+        self.receipts.push((ctx.tx_id, receipts.to_vec()));
+    }
+}
+
+/// Keeps the state changes of every traced transaction
+#[derive(Default)]
+pub struct StateDiffCollector {
+    pub diffs: Vec<(TxId, StateDiff)>,
+}
+
+impl BlockTracer for StateDiffCollector {
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    fn on_state_diff(&mut self, ctx: &TraceContext, diff: &StateDiff) {
+        self.diffs.push((ctx.tx_id, diff.clone()));
+    }
+}
+
+/// Script data for [`call_script`]: the call parameters, followed by the id of the forwarded asset
+pub fn call_data(contract_id: ContractId) -> Vec<u8> {
+    contract_id
+        .iter()
+        .copied()
+        .chain(0u64.to_be_bytes())
+        .chain(0u64.to_be_bytes())
+        .chain(AssetId::zeroed().iter().copied())
+        .collect()
+}
+
+/// Script calling the contract given in [`call_data`] without forwarding coins, then returning
+pub fn call_script() -> Vec<Instruction> {
+    vec![
+        op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+        op::addi(0x11, 0x10, 48),
+        op::call(0x10, RegId::ZERO, 0x11, RegId::CGAS),
+        op::ret(RegId::ONE),
+    ]
+}
+
+/// A file in the temporary directory, removed when dropped
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("{name}-{}.json", std::process::id())))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Transactions whose replay differs from the chain, handled by the replay policy
+
+mod common;
+
+use common::TestBlock;
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_execution_trace::{
+    trace_block, BlockTracer, GasUsage, MemoryDataSource, ReplayPolicy, StateDiff, TraceConfig,
+    TraceContext, TraceDiagnostic, TraceError, TraceFlow, VmView,
+};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+/// Keeps the diagnostics, state changes and receipts of every transaction
+#[derive(Default)]
+struct DivergenceRecorder {
+    diagnostics: Vec<(TxId, TraceDiagnostic)>,
+    diffs: Vec<(TxId, StateDiff)>,
+    receipts: Vec<(TxId, Vec<Receipt>)>,
+}
+
+impl BlockTracer for DivergenceRecorder {
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    fn on_diagnostic(&mut self, ctx: &TraceContext, diagnostic: &TraceDiagnostic) {
+        self.diagnostics.push((ctx.tx_id, diagnostic.clone()));
+    }
+
+    fn on_state_diff(&mut self, ctx: &TraceContext, diff: &StateDiff) {
+        self.diffs.push((ctx.tx_id, diff.clone()));
+    }
+
+    fn on_tx_end(&mut self, ctx: &TraceContext, receipts: &[Receipt], _: &TransactionStatus) {
+        self.receipts.push((ctx.tx_id, receipts.to_vec()));
+    }
+}
+
+/// A block transferring 100 coins to a contract holding 5, then logging its balance.
+/// Both scripts take the contract id followed by the asset id as script data.
+fn block(source: &mut MemoryDataSource) -> [TxId; 2] {
+    let mut block = TestBlock::new(1);
+    let contract_id = block.existing_contract(&[op::ret(RegId::ONE)]);
+    let asset_id = *block.params.base_asset_id();
+    block.existing_balance(contract_id, asset_id, 5);
+    let data: Vec<u8> = contract_id.iter().chain(asset_id.iter()).copied().collect();
+    let transfer_tx = block.script(
+        &[
+            op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+            op::addi(0x11, 0x10, 32),
+            op::movi(0x12, 100),
+            op::tr(0x10, 0x12, 0x11),
+            op::ret(RegId::ONE),
+        ],
+        data.clone(),
+        &[contract_id],
+    );
+    let balance_tx = block.script(
+        &[
+            op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+            op::addi(0x11, 0x10, 32),
+            op::bal(0x12, 0x11, 0x10),
+            op::log(0x12, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ],
+        data,
+        &[contract_id],
+    );
+    block.finish(source);
+    [transfer_tx, balance_tx]
+}
+
+/// Status of the transaction as the source reports it
+fn status_mut(source: &mut MemoryDataSource, tx_id: TxId) -> &mut TransactionStatus {
+    &mut source.transactions.get_mut(&tx_id).unwrap().status
+}
+
+/// Makes the chain report that the script returned 2 instead of 1
+fn tamper_receipts(source: &mut MemoryDataSource, tx_id: TxId) {
+    let TransactionStatus::Success { receipts, .. } = status_mut(source, tx_id) else {
+        panic!("Transaction failed on chain");
+    };
+    let Some(Receipt::Return { val, .. }) = receipts
+        .iter_mut()
+        .find(|receipt| matches!(receipt, Receipt::Return { .. }))
+    else {
+        panic!("No return receipt");
+    };
+    *val = 2;
+}
+
+/// Makes the chain report one more unit of gas and fee than the transaction used.
+/// Returns the usage reported before and after tampering.
+fn tamper_gas(source: &mut MemoryDataSource, tx_id: TxId) -> (GasUsage, GasUsage) {
+    let TransactionStatus::Success {
+        total_gas,
+        total_fee,
+        ..
+    } = status_mut(source, tx_id)
+    else {
+        panic!("Transaction failed on chain");
+    };
+    let actual = GasUsage {
+        total_gas: *total_gas,
+        total_fee: *total_fee,
+    };
+    *total_gas += 1;
+    *total_fee += 1;
+    let expected = GasUsage {
+        total_gas: *total_gas,
+        total_fee: *total_fee,
+    };
+    (actual, expected)
+}
+
+fn logged_balance(receipts: &[Receipt]) -> Word {
+    receipts
+        .iter()
+        .find_map(|receipt| match receipt {
+            Receipt::Log { ra, .. } => Some(*ra),
+            _ => None,
+        })
+        .expect("No log receipt")
+}
+
+async fn trace(source: &MemoryDataSource, config: TraceConfig) -> DivergenceRecorder {
+    let mut tracer = DivergenceRecorder::default();
+    trace_block(source, 1u32.into(), config, &mut tracer)
+        .await
+        .unwrap();
+    tracer
+}
+
+#[tokio::test]
+async fn warnings_keep_the_divergent_state() {
+    let mut source = MemoryDataSource::default();
+    let [transfer_tx, balance_tx] = block(&mut source);
+    tamper_receipts(&mut source, transfer_tx);
+
+    let config = TraceConfig {
+        replay_policy: ReplayPolicy::Warn,
+        ..TraceConfig::default()
+    };
+    let tracer = trace(&source, config).await;
+
+    assert_eq!(tracer.diagnostics.len(), 1);
+    let (tx_id, TraceDiagnostic::ReceiptsMismatch(diff)) = &tracer.diagnostics[0] else {
+        panic!("Unexpected diagnostic: {:?}", tracer.diagnostics[0]);
+    };
+    assert_eq!(*tx_id, transfer_tx);
+    assert_eq!(diff.first_divergent_index, 1);
+    assert_eq!(diff.field_diffs[0].field, "val");
+
+    // Tracing went on, and the transfer was committed
+    assert_eq!(tracer.receipts.len(), 2);
+    assert_eq!(tracer.diffs[0].0, transfer_tx);
+    assert_eq!(tracer.diffs[0].1.balances[0].after, Some(105));
+    assert_eq!(tracer.receipts[1].0, balance_tx);
+    assert_eq!(logged_balance(&tracer.receipts[1].1), 105);
+}
+
+#[tokio::test]
+async fn divergent_state_can_be_discarded() {
+    let mut source = MemoryDataSource::default();
+    let [transfer_tx, balance_tx] = block(&mut source);
+    tamper_receipts(&mut source, transfer_tx);
+
+    let config = TraceConfig {
+        replay_policy: ReplayPolicy::Warn,
+        discard_divergent_state: true,
+        ..TraceConfig::default()
+    };
+    let tracer = trace(&source, config).await;
+
+    assert_eq!(tracer.diffs[0], (transfer_tx, StateDiff::default()));
+    // The balance script now diverges too, as it doesn't see the transfer
+    assert_eq!(logged_balance(&tracer.receipts[1].1), 5);
+    let tx_ids: Vec<_> = tracer.diagnostics.iter().map(|(tx_id, _)| *tx_id).collect();
+    assert_eq!(tx_ids, [transfer_tx, balance_tx]);
+}
+
+#[tokio::test]
+async fn gas_mismatches_fail_strict_replays() {
+    let mut source = MemoryDataSource::default();
+    let [transfer_tx, _] = block(&mut source);
+    let (actual, expected) = tamper_gas(&mut source, transfer_tx);
+
+    let mut tracer = DivergenceRecorder::default();
+    let result = trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer).await;
+
+    assert!(matches!(
+        result,
+        Err(TraceError::GasMismatch {
+            tx_id,
+            expected: ref e,
+            actual: ref a,
+        }) if tx_id == transfer_tx && *e == expected && *a == actual
+    ));
+    assert!(tracer.receipts.is_empty());
+}
+
+#[tokio::test]
+async fn gas_mismatches_are_reported_as_warnings() {
+    let mut source = MemoryDataSource::default();
+    let [transfer_tx, _] = block(&mut source);
+    let (actual, expected) = tamper_gas(&mut source, transfer_tx);
+
+    let config = TraceConfig {
+        replay_policy: ReplayPolicy::Warn,
+        ..TraceConfig::default()
+    };
+    let tracer = trace(&source, config).await;
+
+    assert_eq!(tracer.diagnostics.len(), 1);
+    let (
+        tx_id,
+        TraceDiagnostic::GasMismatch {
+            expected: e,
+            actual: a,
+        },
+    ) = &tracer.diagnostics[0]
+    else {
+        panic!("Unexpected diagnostic: {:?}", tracer.diagnostics[0]);
+    };
+    assert_eq!(*tx_id, transfer_tx);
+    assert_eq!((*e, *a), (expected, actual));
+    assert_eq!(tracer.receipts.len(), 2);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::TestBlock;
+use fuel_execution_trace::{
+    trace_block, BlockTracer, MemoryDataSource, TraceConfig, TraceContext, TraceFlow, VmView,
+};
+use fuel_vm::{
+    error::SimpleResult,
+    fuel_asm::op,
+    interpreter::{EcalHandler, Memory},
+    prelude::*,
+};
+
+/// Sets the first register operand to 42
+#[derive(Debug, Clone, Copy, Default)]
+struct AnswerEcal;
+
+impl EcalHandler for AnswerEcal {
+    fn ecal<M, S, Tx, V>(
+        vm: &mut Interpreter<M, S, Tx, Self, V>,
+        a: RegId,
+        _b: RegId,
+        _c: RegId,
+        _d: RegId,
+    ) -> SimpleResult<()>
+    where
+        M: Memory,
+    {
+        vm.registers_mut()[a] = 42;
+        Ok(())
+    }
+}
+
+/// Keeps the index and operands of every `ECAL` passed to the tracer
+#[derive(Default)]
+struct EcalRecorder {
+    ecals: Vec<(u64, [RegId; 4])>,
+    receipts: Vec<Receipt>,
+}
+
+impl BlockTracer for EcalRecorder {
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    fn on_ecal(
+        &mut self,
+        _vm: &dyn VmView,
+        ctx: &TraceContext,
+        registers: [RegId; 4],
+    ) -> TraceFlow {
+        self.ecals.push((ctx.tx_instruction_index, registers));
+        TraceFlow::Continue
+    }
+
+    fn on_receipt(
+        &mut self,
+        _vm: &dyn VmView,
+        _ctx: &TraceContext,
+        _index: usize,
+        receipt: &Receipt,
+    ) -> TraceFlow {
+        self.receipts.push(receipt.clone());
+        TraceFlow::Continue
+    }
+}
+
+/// Traces a block with a single script executed with [`AnswerEcal`]
+async fn trace(script: &[Instruction], data: Vec<u8>) -> EcalRecorder {
+    let mut block = TestBlock::new(1);
+    block.ecal_script(script, data, AnswerEcal);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = EcalRecorder::default();
+    let config = TraceConfig::default().with_ecal(AnswerEcal);
+    trace_block(&source, 1u32.into(), config, &mut tracer)
+        .await
+        .unwrap();
+    tracer
+}
+
+#[tokio::test]
+async fn ecal_instructions_are_passed_to_the_tracer() {
+    let tracer = trace(
+        &[
+            op::ecal(0x10, 0x11, 0x12, 0x13),
+            op::log(0x10, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ],
+        Vec::new(),
+    )
+    .await;
+
+    let registers = [0x10, 0x11, 0x12, 0x13].map(RegId::new);
+    assert_eq!(tracer.ecals, [(0, registers)]);
+    assert!(matches!(tracer.receipts[0], Receipt::Log { ra: 42, .. }));
+}
+
+#[tokio::test]
+async fn data_after_the_last_instruction_is_not_an_ecal() {
+    // Once the script returned, `$pc` points to the script data
+    let data: Vec<u8> = op::ecal(0x10, 0x11, 0x12, 0x13).to_bytes().to_vec();
+    let tracer = trace(&[op::noop(), op::ret(RegId::ONE)], data).await;
+
+    assert!(tracer.ecals.is_empty());
+    assert_eq!(tracer.receipts.len(), 2);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::io;
+
+use common::{ReceiptCollector, TempFile, TestBlock};
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_execution_trace::{
+    trace_block, FileDataSource, MemoryDataSource, TraceConfig, FIXTURE_VERSION,
+};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+#[tokio::test]
+async fn recorded_fixtures_can_be_traced() {
+    let mut block = TestBlock::new(1);
+    let (_, contract_id) = block.deploy(&[op::log(RegId::ONE, 0, 0, 0), op::ret(RegId::ONE)]);
+    let call_tx = block.call(contract_id);
+    let mut node = MemoryDataSource::default();
+    block.finish(&mut node);
+
+    let mut recorded = MemoryDataSource::default();
+    recorded.record_block(&node, 1u32.into()).await.unwrap();
+    let file = TempFile::new("fixture-round-trip");
+    FileDataSource::save(&file.0, &recorded).unwrap();
+    let fixture = FileDataSource::open(&file.0).unwrap();
+    assert_eq!(fixture.data().transactions.len(), node.transactions.len());
+
+    let mut tracer = ReceiptCollector::default();
+    trace_block(&fixture, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+    let TransactionStatus::Success { receipts, .. } = &node.transactions[&call_tx].status else {
+        panic!("Call failed on chain");
+    };
+    let traced = tracer.receipts.iter().find(|(tx_id, _)| *tx_id == call_tx);
+    assert_eq!(traced, Some(&(call_tx, receipts.clone())));
+}
+
+#[test]
+fn fixtures_with_unknown_versions_are_rejected() {
+    let file = TempFile::new("fixture-unknown-version");
+    FileDataSource::save(&file.0, &MemoryDataSource::default()).unwrap();
+    let contents = std::fs::read_to_string(&file.0).unwrap();
+    let newer = FIXTURE_VERSION + 1;
+    let contents = contents.replacen(
+        &format!("\"version\":{FIXTURE_VERSION}"),
+        &format!("\"version\":{newer}"),
+        1,
+    );
+    std::fs::write(&file.0, contents).unwrap();
+
+    let error = FileDataSource::open(&file.0).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        error.to_string(),
+        format!("Unsupported fixture version {newer}, expected {FIXTURE_VERSION}")
+    );
+}
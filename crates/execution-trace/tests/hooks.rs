@@ -0,0 +1,390 @@
+mod common;
+
+use common::TestBlock;
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_core_storage::column::Column;
+use fuel_execution_trace::{
+    trace_block, trace_transaction, BlockTracer, InstructionCost, InstructionView,
+    MemoryDataSource, StorageAccess, StorageAccessKind, TraceConfig, TraceContext, TraceFlow,
+    VmView,
+};
+use fuel_vm::{fuel_asm::op, fuel_types::BlockHeight, prelude::*};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    BlockStart(BlockHeight),
+    TxStart(TxId),
+    Instruction(TxId),
+    Receipt(TxId, usize),
+    TxEnd(TxId, usize),
+    BlockEnd(BlockHeight),
+}
+
+#[derive(Default)]
+struct EventRecorder {
+    events: Vec<Event>,
+}
+
+impl BlockTracer for EventRecorder {
+    fn on_block_start(&mut self, block_height: BlockHeight) {
+        self.events.push(Event::BlockStart(block_height));
+    }
+
+    fn on_tx_start(&mut self, ctx: &TraceContext) {
+        self.events.push(Event::TxStart(ctx.tx_id));
+    }
+
+    fn on_instruction(&mut self, _vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        self.events.push(Event::Instruction(ctx.tx_id));
+        TraceFlow::Continue
+    }
+
+    fn on_receipt(
+        &mut self,
+        _vm: &dyn VmView,
+        ctx: &TraceContext,
+        index: usize,
+        _receipt: &Receipt,
+    ) -> TraceFlow {
+        self.events.push(Event::Receipt(ctx.tx_id, index));
+        TraceFlow::Continue
+    }
+
+    fn on_tx_end(&mut self, ctx: &TraceContext, receipts: &[Receipt], _: &TransactionStatus) {
+        self.events.push(Event::TxEnd(ctx.tx_id, receipts.len()));
+    }
+
+    fn on_block_end(&mut self, block_height: BlockHeight) {
+        self.events.push(Event::BlockEnd(block_height));
+    }
+}
+
+impl EventRecorder {
+    /// Events of a transaction between its start and end, which must be instructions and receipts
+    fn steps(&self, tx_id: TxId) -> &[Event] {
+        let start = self
+            .events
+            .iter()
+            .position(|event| *event == Event::TxStart(tx_id))
+            .unwrap();
+        let end = self
+            .events
+            .iter()
+            .position(|event| matches!(event, Event::TxEnd(id, _) if *id == tx_id))
+            .unwrap();
+        &self.events[start + 1..end]
+    }
+}
+
+/// A block deploying a contract, calling it, then running another script
+fn block(source: &mut MemoryDataSource) -> [TxId; 3] {
+    let mut block = TestBlock::new(1);
+    let (deploy_tx, contract_id) =
+        block.deploy(&[op::log(RegId::ONE, 0, 0, 0), op::ret(RegId::ONE)]);
+    let call_tx = block.call(contract_id);
+    let script_tx = block.script(&[op::noop(), op::ret(RegId::ONE)], Vec::new(), &[]);
+    block.finish(source);
+    [deploy_tx, call_tx, script_tx]
+}
+
+#[tokio::test]
+async fn hooks_are_called_in_order() {
+    let mut source = MemoryDataSource::default();
+    let [deploy_tx, call_tx, script_tx] = block(&mut source);
+    let height = BlockHeight::new(1);
+
+    let mut tracer = EventRecorder::default();
+    trace_block(&source, height, TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let boundaries: Vec<_> = tracer
+        .events
+        .iter()
+        .filter(|event| !matches!(event, Event::Instruction(_) | Event::Receipt(..)))
+        .cloned()
+        .collect();
+    assert_eq!(
+        boundaries,
+        [
+            Event::BlockStart(height),
+            Event::TxStart(deploy_tx),
+            Event::TxEnd(deploy_tx, 0),
+            Event::TxStart(call_tx),
+            Event::TxEnd(call_tx, 5),
+            Event::TxStart(script_tx),
+            Event::TxEnd(script_tx, 2),
+            Event::BlockEnd(height),
+        ]
+    );
+
+    for tx_id in [call_tx, script_tx] {
+        let steps = tracer.steps(tx_id);
+        // Receipts are passed in order, before the instruction following the one producing them
+        let receipts: Vec<_> = steps
+            .iter()
+            .filter_map(|event| match event {
+                Event::Receipt(id, index) if *id == tx_id => Some(*index),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(receipts, (0..receipts.len()).collect::<Vec<_>>());
+        assert_eq!(steps.first(), Some(&Event::Instruction(tx_id)));
+        assert_eq!(steps.last(), Some(&Event::Instruction(tx_id)));
+    }
+    assert_eq!(
+        tracer.steps(script_tx),
+        [
+            Event::Instruction(script_tx),
+            Event::Instruction(script_tx),
+            Event::Receipt(script_tx, 0),
+            Event::Receipt(script_tx, 1),
+            Event::Instruction(script_tx),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn only_the_target_transaction_is_traced() {
+    let mut source = MemoryDataSource::default();
+    let [_, call_tx, _] = block(&mut source);
+
+    let mut tracer = EventRecorder::default();
+    // The call only succeeds if the deployment before it was executed
+    trace_transaction(&source, &call_tx, TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let height = BlockHeight::new(1);
+    assert_eq!(tracer.events.first(), Some(&Event::BlockStart(height)));
+    assert_eq!(tracer.events.last(), Some(&Event::BlockEnd(height)));
+    assert_eq!(tracer.events[1], Event::TxStart(call_tx));
+    assert_eq!(
+        tracer.events[tracer.events.len() - 2],
+        Event::TxEnd(call_tx, 5)
+    );
+    assert_eq!(tracer.steps(call_tx).len(), tracer.events.len() - 4);
+    assert!(tracer.steps(call_tx).iter().all(|event| match event {
+        Event::Instruction(tx_id) | Event::Receipt(tx_id, _) => *tx_id == call_tx,
+        _ => false,
+    }));
+}
+
+/// Keeps the storage accesses of every transaction, skipping `skip` at its first instruction
+#[derive(Default)]
+struct AccessRecorder {
+    skip: Option<TxId>,
+    accesses: Vec<(TxId, StorageAccess)>,
+}
+
+impl BlockTracer for AccessRecorder {
+    fn on_instruction(&mut self, _vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        if Some(ctx.tx_id) == self.skip {
+            TraceFlow::SkipTransaction
+        } else {
+            TraceFlow::Continue
+        }
+    }
+
+    fn on_storage_access(
+        &mut self,
+        _vm: &dyn VmView,
+        ctx: &TraceContext,
+        access: &StorageAccess,
+    ) -> TraceFlow {
+        self.accesses.push((ctx.tx_id, access.clone()));
+        TraceFlow::Continue
+    }
+}
+
+fn journal_config() -> TraceConfig {
+    TraceConfig {
+        storage_journal: true,
+        ..TraceConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn storage_accesses_are_attributed_to_the_traced_instruction() {
+    let mut source = MemoryDataSource::default();
+    let [_, call_tx, _] = block(&mut source);
+
+    let mut tracer = AccessRecorder::default();
+    trace_transaction(&source, &call_tx, journal_config(), &mut tracer)
+        .await
+        .unwrap();
+
+    assert!(tracer.accesses.iter().all(|(tx_id, _)| *tx_id == call_tx));
+    // The CALL instruction loads the code of the contract
+    assert!(tracer.accesses.iter().any(|(_, access)| {
+        access.column == Column::ContractsRawCode
+            && access.kind == StorageAccessKind::Read
+            && access.instruction_index == Some(2)
+            && access.contract_id.is_none()
+    }));
+}
+
+#[tokio::test]
+async fn skipped_transactions_stop_reporting_storage_accesses() {
+    let mut source = MemoryDataSource::default();
+    let [_, call_tx, _] = block(&mut source);
+
+    let mut tracer = AccessRecorder {
+        skip: Some(call_tx),
+        ..AccessRecorder::default()
+    };
+    trace_block(&source, 1u32.into(), journal_config(), &mut tracer)
+        .await
+        .unwrap();
+
+    // Only the accesses done while setting up the interpreter reach the first instruction
+    assert!(tracer
+        .accesses
+        .iter()
+        .all(|(tx_id, access)| *tx_id != call_tx || access.instruction_index.is_none()));
+}
+
+/// Keeps the view of every instruction
+#[derive(Default)]
+struct ViewRecorder {
+    views: Vec<InstructionView>,
+}
+
+impl BlockTracer for ViewRecorder {
+    fn on_instruction(&mut self, vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        self.views.push(InstructionView::new(vm, ctx));
+        TraceFlow::Continue
+    }
+}
+
+#[tokio::test]
+async fn nothing_is_decoded_after_the_program_returned() {
+    let mut block = TestBlock::new(1);
+    // The script data right after the script decodes as an instruction
+    let data = op::movi(0x10, 1).to_bytes().to_vec();
+    block.script(&[op::noop(), op::ret(RegId::ONE)], data, &[]);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = ViewRecorder::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let gas_costs = GasCosts::default();
+    let instructions: Vec<_> = tracer
+        .views
+        .iter()
+        .map(|view| (view.instruction, view.cost.clone()))
+        .collect();
+    assert_eq!(
+        instructions,
+        [
+            (
+                Some(op::noop()),
+                Some(InstructionCost::Fixed(gas_costs.noop()))
+            ),
+            (
+                Some(op::ret(RegId::ONE)),
+                Some(InstructionCost::Fixed(gas_costs.ret()))
+            ),
+            (None, None),
+        ]
+    );
+}
+
+/// Panics at the first instruction
+struct PanickingTracer;
+
+impl BlockTracer for PanickingTracer {
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        panic!("tracer failed");
+    }
+}
+
+#[tokio::test]
+#[should_panic(expected = "tracer failed")]
+async fn tracer_panics_reach_the_caller() {
+    let mut source = MemoryDataSource::default();
+    block(&mut source);
+
+    let _ = trace_block(
+        &source,
+        1u32.into(),
+        TraceConfig::default(),
+        &mut PanickingTracer,
+    )
+    .await;
+}
+
+/// Position of an instruction as seen by the tracer
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Position {
+    tx_id: TxId,
+    instruction_index: u64,
+    tx_instruction_index: u64,
+    call_depth: usize,
+    contract_id: Option<ContractId>,
+}
+
+#[derive(Default)]
+struct PositionRecorder {
+    positions: Vec<Position>,
+}
+
+impl BlockTracer for PositionRecorder {
+    fn on_instruction(&mut self, vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        self.positions.push(Position {
+            tx_id: ctx.tx_id,
+            instruction_index: ctx.instruction_index,
+            tx_instruction_index: ctx.tx_instruction_index,
+            call_depth: ctx.call_depth,
+            contract_id: vm.contract_id(),
+        });
+        TraceFlow::Continue
+    }
+}
+
+#[tokio::test]
+async fn calls_are_tracked_in_the_context() {
+    let mut block = TestBlock::new(1);
+    let (_, contract_id) = block.deploy(&[op::log(RegId::ONE, 0, 0, 0), op::ret(RegId::ONE)]);
+    let call_tx = block.call(contract_id);
+    let script_tx = block.script(&[op::noop(), op::ret(RegId::ONE)], Vec::new(), &[]);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = PositionRecorder::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let (call, script): (Vec<_>, Vec<_>) = tracer
+        .positions
+        .iter()
+        .partition(|position| position.tx_id == call_tx);
+    assert!(script.iter().all(|position| position.tx_id == script_tx));
+
+    // The script calls the contract at its third instruction, which logs and returns
+    let depths: Vec<_> = call.iter().map(|position| position.call_depth).collect();
+    assert_eq!(depths, [0, 0, 0, 1, 1, 0, 0]);
+    assert!(call.iter().all(|position| {
+        position.contract_id == (position.call_depth == 1).then_some(contract_id)
+    }));
+
+    // Block counters continue across transactions, transaction counters restart
+    let block_indices: Vec<_> = tracer
+        .positions
+        .iter()
+        .map(|position| position.instruction_index)
+        .collect();
+    assert_eq!(block_indices, (0..10).collect::<Vec<_>>());
+    let tx_indices = |positions: &[&Position]| {
+        positions
+            .iter()
+            .map(|position| position.tx_instruction_index)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tx_indices(&call), (0..7).collect::<Vec<_>>());
+    assert_eq!(tx_indices(&script), (0..3).collect::<Vec<_>>());
+}
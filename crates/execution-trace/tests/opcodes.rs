@@ -0,0 +1,181 @@
+//! Instructions reading contract code, blobs and balances, over storage values encoded like
+//! fuel-core stores them. Replays are strict, so the receipts must match the chain.
+
+mod common;
+
+use common::{ReceiptCollector, TestBlock};
+use fuel_core_storage::column::Column;
+use fuel_execution_trace::{trace_block, MemoryDataSource, StorageError, TraceConfig, TraceError};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+/// Code of the existing contract, 16 bytes long
+fn contract_code() -> Vec<Instruction> {
+    vec![
+        op::movi(0x10, 1),
+        op::movi(0x11, 2),
+        op::add(0x12, 0x10, 0x11),
+        op::ret(0x12),
+    ]
+}
+
+const BLOB: &[u8] = b"some blob data, 24 bytes";
+
+/// Script data: the contract id followed by the blob id
+fn script_data(contract_id: ContractId, blob_id: BlobId) -> Vec<u8> {
+    contract_id.iter().chain(blob_id.iter()).copied().collect()
+}
+
+/// Runs `script` with the contract id in 0x10 and the blob id in 0x11,
+/// returning the receipts of the traced execution
+async fn run(script: &[Instruction]) -> Result<Vec<Receipt>, TraceError> {
+    let mut block = TestBlock::new(1);
+    let contract_id = block.existing_contract(&contract_code());
+    let blob_id = block.existing_blob(BLOB);
+    let script: Vec<_> = [
+        op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+        op::addi(0x11, 0x10, 32),
+    ]
+    .into_iter()
+    .chain(script.iter().copied())
+    .collect();
+    block.script(&script, script_data(contract_id, blob_id), &[contract_id]);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = ReceiptCollector::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer).await?;
+    Ok(tracer.receipts.remove(0).1)
+}
+
+fn logged_value(receipts: &[Receipt]) -> Word {
+    receipts
+        .iter()
+        .find_map(|receipt| match receipt {
+            Receipt::Log { ra, .. } => Some(*ra),
+            _ => None,
+        })
+        .expect("No log receipt")
+}
+
+fn logged_data(receipts: &[Receipt]) -> Vec<u8> {
+    receipts
+        .iter()
+        .find_map(|receipt| match receipt {
+            Receipt::LogData { data, .. } => data.clone().map(|data| data.to_vec()),
+            _ => None,
+        })
+        .expect("No log data receipt")
+}
+
+fn code_bytes() -> Vec<u8> {
+    contract_code().into_iter().collect()
+}
+
+#[tokio::test]
+async fn csiz_reads_the_contract_size() {
+    let receipts = run(&[
+        op::csiz(0x12, 0x10),
+        op::log(0x12, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(logged_value(&receipts), 16);
+}
+
+#[tokio::test]
+async fn ccp_copies_contract_code() {
+    let receipts = run(&[
+        op::movi(0x12, 8),
+        op::aloc(0x12),
+        op::movi(0x13, 4),
+        op::ccp(RegId::HP, 0x10, 0x13, 0x12),
+        op::logd(RegId::ZERO, RegId::ZERO, RegId::HP, 0x12),
+        op::ret(RegId::ONE),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(logged_data(&receipts), code_bytes()[4..12]);
+}
+
+#[tokio::test]
+async fn ldc_loads_contract_code() {
+    let receipts = run(&[
+        op::move_(0x13, RegId::SSP),
+        op::movi(0x12, 16),
+        op::ldc(0x10, RegId::ZERO, 0x12, 0),
+        op::sub(0x14, RegId::SSP, 0x13),
+        op::log(0x14, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::logd(RegId::ZERO, RegId::ZERO, 0x13, 0x12),
+        op::ret(RegId::ONE),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(logged_value(&receipts), 16);
+    assert_eq!(logged_data(&receipts), code_bytes());
+}
+
+#[tokio::test]
+async fn bsiz_reads_the_blob_size() {
+    let receipts = run(&[
+        op::bsiz(0x12, 0x11),
+        op::log(0x12, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::ret(RegId::ONE),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(logged_value(&receipts), 24);
+}
+
+#[tokio::test]
+async fn bldd_copies_blob_data() {
+    let receipts = run(&[
+        op::movi(0x12, 10),
+        op::aloc(0x12),
+        op::movi(0x13, 5),
+        op::bldd(RegId::HP, 0x11, 0x13, 0x12),
+        op::logd(RegId::ZERO, RegId::ZERO, RegId::HP, 0x12),
+        op::ret(RegId::ONE),
+    ])
+    .await
+    .unwrap();
+    assert_eq!(logged_data(&receipts), BLOB[5..15]);
+}
+
+#[tokio::test]
+async fn undecodable_balance_fails() {
+    let mut block = TestBlock::new(1);
+    let contract_id = block.existing_contract(&contract_code());
+    let balance_key: Vec<u8> = contract_id
+        .iter()
+        .chain(AssetId::zeroed().iter())
+        .copied()
+        .collect();
+    // Balances are stored as 8 big-endian bytes
+    block.read(Column::ContractsAssets, balance_key, Some(vec![1; 4]));
+    block.script(
+        &[
+            op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+            op::addi(0x11, 0x10, 32),
+            op::bal(0x12, 0x11, 0x10),
+            op::ret(RegId::ONE),
+        ],
+        contract_id.iter().copied().chain([0; 32]).collect(),
+        &[contract_id],
+    );
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = ReceiptCollector::default();
+    let result = trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer).await;
+    assert!(matches!(
+        result,
+        Err(TraceError::Interpreter {
+            error: InterpreterError::Storage(StorageError::InvalidValue {
+                column: Column::ContractsAssets,
+                ..
+            }),
+            ..
+        })
+    ));
+}
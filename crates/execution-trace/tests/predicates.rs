@@ -0,0 +1,107 @@
+mod common;
+
+use common::TestBlock;
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_execution_trace::{
+    trace_block, BlockTracer, ExecutionPhase, MemoryDataSource, TraceConfig, TraceContext,
+    TraceError, TraceFlow, VmView,
+};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+/// Records the phase of each step, skipping the transaction at the first predicate step if asked
+#[derive(Default)]
+struct PhaseRecorder {
+    skip_in_predicate: bool,
+    steps: Vec<ExecutionPhase>,
+    ended: usize,
+}
+
+impl BlockTracer for PhaseRecorder {
+    fn on_instruction(&mut self, _vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        self.steps.push(ctx.phase.clone());
+        match ctx.phase {
+            ExecutionPhase::Predicate { .. } if self.skip_in_predicate => {
+                TraceFlow::SkipTransaction
+            }
+            _ => TraceFlow::Continue,
+        }
+    }
+
+    fn on_tx_end(&mut self, _ctx: &TraceContext, _receipts: &[Receipt], _: &TransactionStatus) {
+        self.ended += 1;
+    }
+}
+
+fn predicates_config() -> TraceConfig {
+    TraceConfig {
+        predicates: true,
+        ..TraceConfig::default()
+    }
+}
+
+/// A block with a single script spending a coin owned by `predicate`
+fn block(predicate: &[Instruction]) -> (MemoryDataSource, Address) {
+    let mut block = TestBlock::new(1);
+    let (_, owner) = block.predicate_script(&[op::noop(), op::ret(RegId::ONE)], predicate);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+    (source, owner)
+}
+
+#[tokio::test]
+async fn predicates_are_traced_before_the_script() {
+    let (source, owner) = block(&[op::noop(), op::noop(), op::ret(RegId::ONE)]);
+
+    let mut tracer = PhaseRecorder::default();
+    trace_block(&source, 1u32.into(), predicates_config(), &mut tracer)
+        .await
+        .unwrap();
+
+    let predicate = ExecutionPhase::Predicate {
+        input_index: 0,
+        owner,
+    };
+    // One step per predicate instruction and one after the last, then the same for the script
+    assert_eq!(
+        tracer.steps,
+        [vec![predicate; 4], vec![ExecutionPhase::Script; 3]].concat()
+    );
+    assert_eq!(tracer.ended, 1);
+}
+
+#[tokio::test]
+async fn skipping_in_a_predicate_skips_the_whole_transaction() {
+    let (source, _) = block(&[op::noop(), op::ret(RegId::ONE)]);
+
+    let mut tracer = PhaseRecorder {
+        skip_in_predicate: true,
+        ..PhaseRecorder::default()
+    };
+    trace_block(&source, 1u32.into(), predicates_config(), &mut tracer)
+        .await
+        .unwrap();
+
+    assert_eq!(tracer.steps.len(), 1);
+    assert!(matches!(tracer.steps[0], ExecutionPhase::Predicate { .. }));
+    // The transaction is still executed and checked against the chain
+    assert_eq!(tracer.ended, 1);
+}
+
+#[tokio::test]
+async fn predicate_errors_report_the_failing_instruction() {
+    // Predicates can't write contract storage
+    let (source, _) = block(&[op::noop(), op::sww(RegId::ZERO, 0x10, RegId::ZERO)]);
+
+    let mut tracer = PhaseRecorder::default();
+    let result = trace_block(&source, 1u32.into(), predicates_config(), &mut tracer).await;
+
+    assert!(matches!(
+        result,
+        Err(TraceError::Interpreter {
+            instruction_index: Some(1),
+            error: InterpreterError::PanicInstruction(ref panic),
+            ..
+        }) if *panic.reason() == PanicReason::ContractInstructionNotAllowed
+    ));
+    assert_eq!(tracer.ended, 0);
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{ReceiptCollector, StateDiffCollector, TestBlock};
+use fuel_core_client::client::types::TransactionStatus;
+use fuel_core_storage::column::Column;
+use fuel_core_types::tai64::Tai64;
+use fuel_execution_trace::{
+    trace_block, MemoryDataSource, ReplayPolicy, StorageError, TraceConfig, TraceError,
+};
+use fuel_vm::{fuel_asm::op, fuel_types::BlockHeight, prelude::*};
+
+#[tokio::test]
+async fn contract_deployed_earlier_in_the_block_can_be_called() {
+    let mut block = TestBlock::new(1);
+    let (_, contract_id) = block.deploy(&[op::log(RegId::ONE, 0, 0, 0), op::ret(RegId::ONE)]);
+    let call_tx = block.call(contract_id);
+    let mut source = MemoryDataSource::default();
+    let expected = match &source_status(&mut source, block, call_tx) {
+        TransactionStatus::Success { receipts, .. } => receipts.clone(),
+        status => panic!("Call failed on chain: {status:?}"),
+    };
+
+    let mut tracer = ReceiptCollector::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    assert_eq!(tracer.receipts.len(), 2);
+    let (tx_id, receipts) = &tracer.receipts[1];
+    assert_eq!(*tx_id, call_tx);
+    assert_eq!(*receipts, expected);
+    assert!(receipts
+        .iter()
+        .any(|receipt| matches!(receipt, Receipt::Log { id, .. } if *id == contract_id)));
+}
+
+#[tokio::test]
+async fn blob_created_earlier_in_the_block_can_be_read() {
+    let mut block = TestBlock::new(1);
+    let (blob_tx, blob_id) = block.blob(b"blob data");
+    let script_tx = block.script(
+        &[
+            op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+            op::bsiz(0x11, 0x10),
+            op::log(0x11, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+            op::ret(RegId::ONE),
+        ],
+        blob_id.to_vec(),
+        &[],
+    );
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    // Replays are strict, so the script logged the size of the blob like on chain
+    let mut tracer = StateDiffCollector::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let tx_ids: Vec<_> = tracer.diffs.iter().map(|(tx_id, _)| *tx_id).collect();
+    assert_eq!(tx_ids, [blob_tx, script_tx]);
+    assert_eq!(tracer.diffs[0].1.created_blobs, [blob_id]);
+    assert!(tracer.diffs[1].1.is_empty());
+}
+
+#[tokio::test]
+async fn uploaded_bytecode_can_be_used_for_an_upgrade() {
+    let mut block = TestBlock::new(1);
+    let (upload_tx, root) = block.upload(&[0xab; 100]);
+    let upgrade_tx = block.upgrade(root);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    // The upgrade fails unless the upload before it was replayed
+    let mut tracer = ReceiptCollector::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+
+    let tx_ids: Vec<_> = tracer.receipts.iter().map(|(tx_id, _)| *tx_id).collect();
+    assert_eq!(tx_ids, [upload_tx, upgrade_tx]);
+}
+
+/// Logs the timestamp and the hash of block 5
+fn past_block_script() -> Vec<Instruction> {
+    vec![
+        op::movi(0x10, 5),
+        op::time(0x11, 0x10),
+        op::log(0x11, RegId::ZERO, RegId::ZERO, RegId::ZERO),
+        op::movi(0x12, 32),
+        op::aloc(0x12),
+        op::bhsh(RegId::HP, 0x10),
+        op::logd(RegId::ZERO, RegId::ZERO, RegId::HP, 0x12),
+        op::ret(RegId::ONE),
+    ]
+}
+
+#[tokio::test]
+async fn past_blocks_come_from_the_replay() {
+    let mut block = TestBlock::new(10);
+    let time = Tai64::from_unix(1_600_000_000);
+    let block_id = block.past_block(5, time);
+    block.script(&past_block_script(), Vec::new(), &[]);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    // fuel-vm's test storage makes up past blocks, so the receipts on chain can't match
+    let config = TraceConfig {
+        replay_policy: ReplayPolicy::Ignore,
+        ..TraceConfig::default()
+    };
+    let mut tracer = ReceiptCollector::default();
+    trace_block(&source, 10u32.into(), config, &mut tracer)
+        .await
+        .unwrap();
+
+    let (_, receipts) = &tracer.receipts[0];
+    assert!(matches!(receipts[0], Receipt::Log { ra, .. } if ra == time.0));
+    assert!(matches!(
+        &receipts[1],
+        Receipt::LogData { data: Some(data), .. } if **data == *block_id
+    ));
+}
+
+/// Traces the past block script, with `block_5` as what fuel-core read for block 5
+async fn past_block_error(block_5: Option<Option<Vec<u8>>>) -> StorageError {
+    let mut block = TestBlock::new(10);
+    if let Some(value) = block_5 {
+        block.read(Column::FuelBlocks, BlockHeight::new(5).to_bytes(), value);
+    }
+    block.script(&past_block_script(), Vec::new(), &[]);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let mut tracer = ReceiptCollector::default();
+    match trace_block(&source, 10u32.into(), TraceConfig::default(), &mut tracer).await {
+        Err(TraceError::Interpreter {
+            error: InterpreterError::Storage(error),
+            ..
+        }) => error,
+        result => panic!("Expected a storage error, got {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn past_block_not_in_the_replay_fails() {
+    assert!(matches!(
+        past_block_error(None).await,
+        StorageError::MissingReplayKey {
+            column: Column::FuelBlocks,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn past_block_missing_on_chain_fails() {
+    assert!(matches!(
+        past_block_error(Some(None)).await,
+        StorageError::MissingBlockHeader(height) if height == BlockHeight::new(5)
+    ));
+}
+
+#[tokio::test]
+async fn undecodable_past_block_fails() {
+    assert!(matches!(
+        past_block_error(Some(Some(vec![0xff; 4]))).await,
+        StorageError::InvalidValue {
+            column: Column::FuelBlocks,
+            ..
+        }
+    ));
+}
+
+/// Finishes the block and returns the status of `tx_id`
+fn source_status(
+    source: &mut MemoryDataSource,
+    block: TestBlock,
+    tx_id: TxId,
+) -> TransactionStatus {
+    block.finish(source);
+    source.transactions[&tx_id].status.clone()
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{ReceiptCollector, TempFile, TestBlock};
+use fuel_core_client::client::types::{TransactionStatus, TransactionType};
+use fuel_execution_trace::{
+    trace_block, trace_snapshot_transaction, BlockTracer, MemoryDataSource, StorageError,
+    StorageSnapshot, TraceConfig, TraceContext, TraceError, TraceFlow, VmView,
+};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+/// Keeps the snapshot taken before every transaction
+#[derive(Default)]
+struct SnapshotCollector {
+    snapshots: Vec<StorageSnapshot>,
+}
+
+impl BlockTracer for SnapshotCollector {
+    fn on_snapshot(&mut self, _ctx: &TraceContext, snapshot: &StorageSnapshot) {
+        self.snapshots.push(snapshot.clone());
+    }
+
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+}
+
+/// A block deploying a contract and calling it. Returns the snapshots taken
+/// before each of the two transactions, and the call with its on-chain status.
+async fn snapshots() -> (Vec<StorageSnapshot>, Transaction, TransactionStatus) {
+    let mut block = TestBlock::new(1);
+    let (_, contract_id) = block.deploy(&[op::log(RegId::ONE, 0, 0, 0), op::ret(RegId::ONE)]);
+    let call_tx = block.call(contract_id);
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+
+    let config = TraceConfig {
+        snapshots: true,
+        ..TraceConfig::default()
+    };
+    let mut tracer = SnapshotCollector::default();
+    trace_block(&source, 1u32.into(), config, &mut tracer)
+        .await
+        .unwrap();
+
+    let call = source.transactions[&call_tx].clone();
+    let TransactionType::Known(tx) = call.transaction else {
+        panic!("Unknown transaction type");
+    };
+    (tracer.snapshots, tx, call.status)
+}
+
+#[tokio::test]
+async fn snapshots_survive_a_round_trip() {
+    let (snapshots, _, _) = snapshots().await;
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[1].tx_index, 1);
+
+    let file = TempFile::new("snapshot-round-trip");
+    snapshots[1].save(&file.0).unwrap();
+    assert_eq!(StorageSnapshot::open(&file.0).unwrap(), snapshots[1]);
+}
+
+#[tokio::test]
+async fn transaction_traced_on_a_snapshot_sees_its_state() {
+    let (snapshots, call, status) = snapshots().await;
+    let TransactionStatus::Success { receipts, .. } = status else {
+        panic!("Call failed on chain: {status:?}");
+    };
+
+    // The contract is only deployed in the state before the call
+    let mut tracer = ReceiptCollector::default();
+    trace_snapshot_transaction(
+        snapshots[1].clone(),
+        call.clone(),
+        TraceConfig::default(),
+        &mut tracer,
+    )
+    .unwrap();
+    assert_eq!(tracer.receipts.len(), 1);
+    assert_eq!(tracer.receipts[0].1, receipts);
+
+    let mut tracer = ReceiptCollector::default();
+    let result = trace_snapshot_transaction(
+        snapshots[0].clone(),
+        call,
+        TraceConfig::default(),
+        &mut tracer,
+    );
+    assert!(matches!(result, Err(TraceError::Interpreter { .. })));
+}
+
+#[tokio::test]
+async fn snapshots_with_unknown_columns_are_rejected() {
+    let (mut snapshots, call, _) = snapshots().await;
+    let mut snapshot = snapshots.remove(1);
+    snapshot.entries[0].column = 9999;
+
+    let file = TempFile::new("snapshot-unknown-column");
+    snapshot.save(&file.0).unwrap();
+    let snapshot = StorageSnapshot::open(&file.0).unwrap();
+
+    let mut tracer = ReceiptCollector::default();
+    assert!(matches!(
+        trace_snapshot_transaction(snapshot, call, TraceConfig::default(), &mut tracer),
+        Err(TraceError::Storage(StorageError::InvalidColumn(9999)))
+    ));
+}
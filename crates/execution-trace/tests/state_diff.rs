@@ -0,0 +1,95 @@
+mod common;
+
+use common::{StateDiffCollector, TestBlock};
+use fuel_core_storage::column::Column;
+use fuel_execution_trace::{
+    trace_block, BalanceChange, MemoryDataSource, SlotChange, StateDiff, TraceConfig,
+};
+use fuel_vm::{fuel_asm::op, prelude::*};
+
+/// Traces the block, returning the state changes of each transaction
+async fn diffs(block: TestBlock) -> Vec<(TxId, StateDiff)> {
+    let mut source = MemoryDataSource::default();
+    block.finish(&mut source);
+    let mut tracer = StateDiffCollector::default();
+    trace_block(&source, 1u32.into(), TraceConfig::default(), &mut tracer)
+        .await
+        .unwrap();
+    tracer.diffs
+}
+
+#[tokio::test]
+async fn deployments_create_contracts() {
+    let mut block = TestBlock::new(1);
+    let (deploy_tx, contract_id) = block.deploy(&[op::ret(RegId::ONE)]);
+
+    let diffs = diffs(block).await;
+    assert_eq!(diffs[0].0, deploy_tx);
+    assert_eq!(diffs[0].1.created_contracts, [contract_id]);
+    assert!(diffs[0].1.storage_slots.is_empty());
+}
+
+#[tokio::test]
+async fn contract_calls_write_storage_slots() {
+    let mut block = TestBlock::new(1);
+    // Sets the slot with an all-zero key to 1
+    let (_, contract_id) = block.deploy(&[
+        op::movi(0x10, 32),
+        op::aloc(0x10),
+        op::sww(RegId::HP, 0x11, RegId::ONE),
+        op::ret(RegId::ONE),
+    ]);
+    let slot_key: Vec<u8> = contract_id.iter().copied().chain([0; 32]).collect();
+    block.read(Column::ContractsState, slot_key, None);
+    let call_tx = block.call(contract_id);
+
+    let diffs = diffs(block).await;
+    assert_eq!(diffs[1].0, call_tx);
+    let value: Vec<u8> = 1u64.to_be_bytes().into_iter().chain([0; 24]).collect();
+    assert_eq!(
+        diffs[1].1.storage_slots,
+        [SlotChange {
+            contract_id,
+            key: Bytes32::zeroed(),
+            before: None,
+            after: Some(value),
+        }]
+    );
+    // The call writes the missing balance of the forwarded asset as zero, which isn't a change
+    assert!(diffs[1].1.balances.is_empty());
+    assert!(diffs[1].1.created_contracts.is_empty());
+}
+
+#[tokio::test]
+async fn transfers_change_balances() {
+    let mut block = TestBlock::new(1);
+    let contract_id = block.existing_contract(&[op::ret(RegId::ONE)]);
+    let asset_id = *block.params.base_asset_id();
+    block.existing_balance(contract_id, asset_id, 5);
+    // Transfers 100 coins of the asset following the contract id in the script data
+    block.script(
+        &[
+            op::gtf_args(0x10, RegId::ZERO, GTFArgs::ScriptData),
+            op::addi(0x11, 0x10, 32),
+            op::movi(0x12, 100),
+            op::tr(0x10, 0x12, 0x11),
+            op::ret(RegId::ONE),
+        ],
+        contract_id.iter().chain(asset_id.iter()).copied().collect(),
+        &[contract_id],
+    );
+
+    let diffs = diffs(block).await;
+    assert_eq!(
+        diffs[0].1,
+        StateDiff {
+            balances: vec![BalanceChange {
+                contract_id,
+                asset_id,
+                before: Some(5),
+                after: Some(105),
+            }],
+            ..StateDiff::default()
+        }
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::TestBlock;
+use fuel_execution_trace::{
+    trace_blocks, BlockTracer, MemoryDataSource, TraceConfig, TraceContext, TraceFlow, VmView,
+};
+use fuel_vm::{fuel_asm::op, fuel_types::BlockHeight, prelude::*};
+
+/// Records block boundaries, stopping at the first instruction of `stop_at`
+#[derive(Default)]
+struct BlockRecorder {
+    stop_at: Option<BlockHeight>,
+    started: Vec<BlockHeight>,
+    ended: Vec<BlockHeight>,
+}
+
+impl BlockTracer for BlockRecorder {
+    fn on_block_start(&mut self, block_height: BlockHeight) {
+        self.started.push(block_height);
+    }
+
+    fn on_instruction(&mut self, _vm: &dyn VmView, ctx: &TraceContext) -> TraceFlow {
+        if Some(ctx.block_height) == self.stop_at {
+            TraceFlow::StopBlock
+        } else {
+            TraceFlow::Continue
+        }
+    }
+
+    fn on_block_end(&mut self, block_height: BlockHeight) {
+        self.ended.push(block_height);
+    }
+}
+
+/// Blocks 1 to `count`, each with a single script
+fn blocks(count: u32) -> MemoryDataSource {
+    let mut source = MemoryDataSource::default();
+    for height in 1..=count {
+        let mut block = TestBlock::new(height);
+        block.script(&[op::ret(RegId::ONE)], Vec::new(), &[]);
+        block.finish(&mut source);
+    }
+    source
+}
+
+fn heights(range: std::ops::RangeInclusive<u32>) -> Vec<BlockHeight> {
+    range.map(BlockHeight::new).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocks_are_traced_in_order() {
+    let source = blocks(8);
+    let config = TraceConfig {
+        prefetch_blocks: 3,
+        ..TraceConfig::default()
+    };
+
+    let mut tracer = BlockRecorder::default();
+    trace_blocks(&source, 2u32.into()..=7u32.into(), config, &mut tracer)
+        .await
+        .unwrap();
+
+    assert_eq!(tracer.started, heights(2..=7));
+    assert_eq!(tracer.ended, heights(2..=7));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stopping_a_block_stops_the_range() {
+    let source = blocks(6);
+
+    let mut tracer = BlockRecorder {
+        stop_at: Some(3u32.into()),
+        ..BlockRecorder::default()
+    };
+    trace_blocks(
+        &source,
+        1u32.into()..=6u32.into(),
+        TraceConfig::default(),
+        &mut tracer,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(tracer.started, heights(1..=3));
+    assert_eq!(tracer.ended, heights(1..=3));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn missing_block_fails_after_the_previous_ones() {
+    let source = blocks(3);
+
+    let mut tracer = BlockRecorder::default();
+    let result = trace_blocks(
+        &source,
+        1u32.into()..=5u32.into(),
+        TraceConfig::default(),
+        &mut tracer,
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(fuel_execution_trace::TraceError::NoSuchBlock)
+    ));
+    assert_eq!(tracer.started, heights(1..=3));
+}
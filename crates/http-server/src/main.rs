@@ -22,7 +22,7 @@ use axum::{
 };
 use clap::Parser;
 use fuel_core_client::client::FuelClient;
-use fuel_execution_trace::TraceError;
+use fuel_execution_trace::{ReceiptDiff, TraceError};
 use fuel_vm::prelude::ContractId;
 use serde::Serialize;
 use tracing_subscriber::EnvFilter;
@@ -65,15 +65,20 @@ impl From<TraceError> for AppError {
 struct ErrorResponse {
     #[schema(examples("Error message"))]
     message: String,
+    /// Receipt differences, present when local execution diverged from the chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    receipt_diff: Option<Box<ReceiptDiff>>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let mut receipt_diff = None;
         let (status, message) = match self {
             AppError::JsonRejection(rejection) => (rejection.status(), rejection.body_text()),
             AppError::Health => (
                 StatusCode::BAD_GATEWAY,
-                format!("request to fuel-core failed"),
+                "request to fuel-core failed".to_string(),
             ),
             AppError::InvalidAbiJson { contract, error } => (
                 StatusCode::BAD_REQUEST,
@@ -84,11 +89,28 @@ impl IntoResponse for AppError {
                     StatusCode::BAD_GATEWAY,
                     format!("request to fuel-core failed: {}", error),
                 ),
-                TraceError::NoSuchBlock => (StatusCode::NOT_FOUND, format!("Block not found")),
-                TraceError::ReceiptsMismatch(tx, _) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Receipts mismatch for {tx:?}"),
+                TraceError::NoSuchBlock => (StatusCode::NOT_FOUND, "Block not found".to_string()),
+                TraceError::NoSuchTransaction(tx) => {
+                    (StatusCode::NOT_FOUND, format!("Transaction {tx} not found"))
+                }
+                TraceError::TransactionNotIncluded(tx) => (
+                    StatusCode::BAD_REQUEST,
+                    format!("Transaction {tx} is not included in a block"),
                 ),
+                TraceError::ReceiptsMismatch { tx_id, diff } => {
+                    let message = format!(
+                        "Receipts mismatch for {tx_id} at index {}",
+                        diff.first_divergent_index
+                    );
+                    receipt_diff = Some(diff);
+                    (StatusCode::INTERNAL_SERVER_ERROR, message)
+                }
+                err @ TraceError::Interpreter { .. } => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                }
+                err @ TraceError::Unsupported { .. } => {
+                    (StatusCode::NOT_IMPLEMENTED, err.to_string())
+                }
                 other => (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     format!("unable to process: {other:?}"),
@@ -96,14 +118,25 @@ impl IntoResponse for AppError {
             },
         };
 
-        (status, AppJson(ErrorResponse { message })).into_response()
+        (
+            status,
+            AppJson(ErrorResponse {
+                message,
+                receipt_diff,
+            }),
+        )
+            .into_response()
     }
 }
 
 #[derive(OpenApi)]
 #[openapi(
     info(title = "Execution tracing proxy for fuel-core"),
-    paths(routes::health::route, routes::trace_block::route,)
+    paths(
+        routes::health::route,
+        routes::trace_block::route,
+        routes::trace_transaction::route,
+    )
 )]
 struct ApiDoc;
 
@@ -158,7 +191,14 @@ async fn main() -> anyhow::Result<()> {
         )
         .route(
             "/v1/trace",
-            post(|path| routes::trace_block::route(client, path)),
+            post({
+                let client = client.clone();
+                |path| routes::trace_block::route(client, path)
+            }),
+        )
+        .route(
+            "/v1/trace_transaction",
+            post(|path| routes::trace_transaction::route(client, path)),
         )
         .fallback((StatusCode::NOT_FOUND, "404 NOT FOUND"));
 
@@ -1,2 +1,3 @@
 pub mod health;
 pub mod trace_block;
+pub mod trace_transaction;
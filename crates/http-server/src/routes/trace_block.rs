@@ -1,5 +1,5 @@
 use fuel_core_client::client::FuelClient;
-use fuel_execution_trace::trace_block;
+use fuel_execution_trace::{trace_block, TraceConfig};
 use fuel_vm::{fuel_types::BlockHeight, prelude::ContractId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -57,7 +57,7 @@ pub async fn route(
 
     let mut tracers = payload.trace.initialize(abis);
 
-    trace_block(&client, block_height, |vm| tracers.callback(vm)).await?;
+    trace_block(&client, block_height, TraceConfig::default(), &mut tracers).await?;
 
     let events = tracers.into_events();
     Ok(AppJson(BlockTrace { events }))
@@ -0,0 +1,69 @@
+use fuel_core_client::client::FuelClient;
+use fuel_execution_trace::{trace_transaction, TraceConfig};
+use fuel_vm::prelude::{ContractId, TxId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::{
+    tracers::{self, Abi, TraceEvent},
+    AppError, AppJson, ErrorResponse,
+};
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct TraceTransaction {
+    /// The abi json files are taken as strings to avoid client having to re-serialize them
+    #[serde(default)]
+    #[schema(value_type = Object, examples(json!({
+        "3aa298739660ff73d0a6d8d93f58620a88a504d8bb4b43632cfd52fa82d408cc": "..",
+    })))]
+    abis: HashMap<ContractId, String>,
+    /// The id of the transaction to trace
+    #[schema(value_type = String)]
+    transaction: TxId,
+    /// The options for the trace
+    trace: tracers::TraceOptions,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionTrace {
+    events: Vec<TraceEvent>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/trace_transaction",
+    request_body = TraceTransaction,
+    responses(
+        (status = OK, description = "Tracing successful", body = TransactionTrace),
+        (status = NOT_FOUND, description = "Requested transaction was not found", body = ErrorResponse),
+        (status = BAD_GATEWAY, description = "Request to fuel-core failed", body = ErrorResponse),
+        (status = BAD_REQUEST, description = "Malformed request", body = ErrorResponse),
+    ),
+)]
+pub async fn route(
+    client: FuelClient,
+    AppJson(payload): AppJson<TraceTransaction>,
+) -> Result<AppJson<TransactionTrace>, AppError> {
+    let mut abis = HashMap::new();
+    for (contract, abi_json) in payload.abis {
+        let abi = Abi::from_json(&abi_json).map_err(|err| AppError::InvalidAbiJson {
+            contract,
+            error: err,
+        })?;
+        abis.insert(contract, abi);
+    }
+
+    let mut tracers = payload.trace.initialize(abis);
+
+    trace_transaction(
+        &client,
+        &payload.transaction,
+        TraceConfig::default(),
+        &mut tracers,
+    )
+    .await?;
+
+    let events = tracers.into_events();
+    Ok(AppJson(TransactionTrace { events }))
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use fuel_execution_trace::{MemoryReader, Vm};
+use fuel_execution_trace::{MemoryReader, TraceContext, VmView};
 use fuel_vm::prelude::{ContractId, Receipt};
 use fuels::{
     core::codec::{ABIDecoder, DecoderConfig},
@@ -11,7 +11,6 @@ use super::{Abi, TraceEvent, Tracer};
 
 #[derive(Default)]
 pub struct CallRetTracer {
-    seen_receipt_count: usize,
     return_type_callstack: Vec<StackFrame>,
 }
 
@@ -21,25 +20,36 @@ enum StackFrame {
 }
 
 impl Tracer for CallRetTracer {
-    fn callback(&mut self, vm: &Vm, abis: &HashMap<ContractId, Abi>) -> Vec<TraceEvent> {
-        let mut result = Vec::new();
-        while self.seen_receipt_count < vm.receipts().len() {
-            result.extend(self.handle_latest_receipt(vm, abis).into_iter());
-            self.seen_receipt_count += 1;
-        }
-        result
+    fn on_tx_start(&mut self) {
+        self.return_type_callstack.clear();
+    }
+
+    fn on_receipt(
+        &mut self,
+        vm: &dyn VmView,
+        ctx: &TraceContext,
+        index: usize,
+        receipt: &Receipt,
+        abis: &HashMap<ContractId, Abi>,
+    ) -> Vec<TraceEvent> {
+        self.handle_receipt(vm, ctx, index, receipt, abis)
+            .into_iter()
+            .collect()
     }
 }
 
 impl CallRetTracer {
-    fn handle_latest_receipt(
+    fn handle_receipt(
         &mut self,
-        vm: &Vm,
+        vm: &dyn VmView,
+        ctx: &TraceContext,
+        index: usize,
+        receipt: &Receipt,
         abis: &HashMap<ContractId, Abi>,
     ) -> Option<TraceEvent> {
         let decoder = ABIDecoder::new(DecoderConfig::default());
 
-        match vm.receipts()[self.seen_receipt_count] {
+        match *receipt {
             Receipt::Call {
                 to, param1, param2, ..
             } => {
@@ -64,7 +74,7 @@ impl CallRetTracer {
                         .iter()
                         .map(|type_| {
                             decoder
-                                .decode(&type_, args_reader.clone())
+                                .decode(type_, args_reader.clone())
                                 .map(|t| t.to_string())
                         })
                         .collect::<Result<_, _>>()
@@ -75,7 +85,8 @@ impl CallRetTracer {
                 };
 
                 Some(TraceEvent::Call {
-                    receipt: self.seen_receipt_count,
+                    transaction: ctx.tx_id,
+                    receipt: index,
                     method,
                     arguments,
                 })
@@ -84,7 +95,8 @@ impl CallRetTracer {
             Receipt::Return { .. } if !self.return_type_callstack.is_empty() => {
                 let _ = self.return_type_callstack.pop().unwrap();
                 Some(TraceEvent::Return {
-                    receipt: self.seen_receipt_count,
+                    transaction: ctx.tx_id,
+                    receipt: index,
                     value: None,
                 })
             }
@@ -103,7 +115,8 @@ impl CallRetTracer {
                 };
 
                 Some(TraceEvent::Return {
-                    receipt: self.seen_receipt_count,
+                    transaction: ctx.tx_id,
+                    receipt: index,
                     value: return_value,
                 })
             }
@@ -123,7 +136,7 @@ impl Signature {
 
         let mut parameters = Vec::new();
         for param in &func.inputs {
-            parameters.push(ParamType::try_from_type_application(&param, &abi.type_lookup).ok()?);
+            parameters.push(ParamType::try_from_type_application(param, &abi.type_lookup).ok()?);
         }
 
         let returns = ParamType::try_from_type_application(&func.output, &abi.type_lookup).ok()?;
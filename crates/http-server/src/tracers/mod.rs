@@ -1,11 +1,11 @@
-use fuel_execution_trace::Vm;
+use fuel_execution_trace::{BlockTracer, TraceContext, TraceFlow, VmView};
 use std::collections::HashMap;
 
 use fuel_abi_types::abi::{
     program::ProgramABI,
     unified_program::{UnifiedProgramABI, UnifiedTypeDeclaration},
 };
-use fuel_vm::prelude::ContractId;
+use fuel_vm::prelude::{ContractId, Receipt, TxId};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -31,7 +31,17 @@ impl TraceOptions {
 }
 
 trait Tracer: Send + Sync + 'static {
-    fn callback(&mut self, vm: &Vm, abis: &HashMap<ContractId, Abi>) -> Vec<TraceEvent>;
+    /// Resets any per-transaction state
+    fn on_tx_start(&mut self) {}
+
+    fn on_receipt(
+        &mut self,
+        vm: &dyn VmView,
+        ctx: &TraceContext,
+        index: usize,
+        receipt: &Receipt,
+        abis: &HashMap<ContractId, Abi>,
+    ) -> Vec<TraceEvent>;
 }
 
 pub struct Abi {
@@ -42,7 +52,7 @@ pub struct Abi {
 }
 impl Abi {
     pub fn from_json(json: &str) -> Result<Self, String> {
-        let program: ProgramABI = serde_json::from_str(&json).map_err(|err| format!("{}", err))?;
+        let program: ProgramABI = serde_json::from_str(json).map_err(|err| format!("{}", err))?;
 
         let unified = UnifiedProgramABI::from_counterpart(&program)
             .map_err(|err| format!("Conversion to unified format failed: {}", err))?;
@@ -67,13 +77,33 @@ pub struct Tracers {
     output: Vec<TraceEvent>,
 }
 
-impl Tracers {
-    pub fn callback(&mut self, vm: &fuel_execution_trace::Vm) {
+impl BlockTracer for Tracers {
+    fn on_tx_start(&mut self, _ctx: &TraceContext) {
+        for tracer in &mut self.tracers {
+            tracer.on_tx_start();
+        }
+    }
+
+    fn on_instruction(&mut self, _vm: &dyn VmView, _ctx: &TraceContext) -> TraceFlow {
+        TraceFlow::Continue
+    }
+
+    fn on_receipt(
+        &mut self,
+        vm: &dyn VmView,
+        ctx: &TraceContext,
+        index: usize,
+        receipt: &Receipt,
+    ) -> TraceFlow {
         for tracer in &mut self.tracers {
-            self.output.extend(tracer.callback(vm, &self.abis));
+            self.output
+                .extend(tracer.on_receipt(vm, ctx, index, receipt, &self.abis));
         }
+        TraceFlow::Continue
     }
+}
 
+impl Tracers {
     pub fn into_events(self) -> Vec<TraceEvent> {
         self.output
     }
@@ -83,7 +113,10 @@ impl Tracers {
 #[serde(rename_all = "snake_case")]
 pub enum TraceEvent {
     Call {
-        /// Which receipt this call corresponds to.
+        /// Transaction the call was made in.
+        #[schema(value_type = String)]
+        transaction: TxId,
+        /// Which receipt of the transaction this call corresponds to.
         #[schema(examples(0))]
         receipt: usize,
         /// Method being called. `None` if param1 doesn't point to a string.
@@ -95,7 +128,10 @@ pub enum TraceEvent {
         arguments: Option<Vec<String>>,
     },
     Return {
-        /// Which receipt this call corresponds to.
+        /// Transaction the call was made in.
+        #[schema(value_type = String)]
+        transaction: TxId,
+        /// Which receipt of the transaction this call corresponds to.
         #[schema(examples(1))]
         receipt: usize,
         /// Return value. `None` if unknown ABI or invalid form.